@@ -0,0 +1,253 @@
+use crate::parse_config;
+use crate::logging::{Sample, file, format};
+use crate::metrics::{interval::IntervalMetrics, warn::WarnMetrics};
+use crate::parse_config::{Config, ConfigMode};
+use crate::reload::ConfigSources;
+use sysinfo::{System, SystemExt};
+use std::time::Duration;
+
+
+pub const USAGE: &str = "Usage: rust-server [COMMAND] [OPTIONS]
+
+Commands:
+    run             Watch the server with the config (default)
+    check           Check the config and list every problem it has
+    once            Take a single sample of every mode and print it
+    print-config    Print the config which would be used, with the secrets masked
+
+Options:
+    --env <FILE>            The .env file to use [default: ./.env, none with --config]
+    --config <FILE>         A TOML or YAML config file, used for the variables which aren't in the env
+    --set <KEY=VALUE>       Set a variable, over the env and config files (can be passed more than once)
+    --format <text|json>    How the once command prints the sample [default: text]
+    -h, --help              Print this help";
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Command {
+    Run,
+    Check,
+    Once(OnceFormat),
+    PrintConfig,
+    Help,
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OnceFormat {
+    // The same text as the file logs
+    Text,
+
+    // One JSON object per mode, the same as the jsonl file logs
+    Json,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// The command line arguments
+pub struct Cli {
+    pub command: Command,
+
+    // The .env file, None uses ./.env if there is one and no config file is passed
+    pub env_file: Option<String>,
+
+    pub config_file: Option<String>,
+
+    // The --set variables, in the order they were passed
+    pub overrides: Vec<(String, String)>,
+}
+
+
+// Parse the arguments, without the program name. The command can be anywhere between the options.
+pub fn parse_args(args: &[String]) -> Result<Cli, String> {
+    let mut command = None;
+    let mut format = None;
+    let mut cli = Cli { command: Command::Run, env_file: None, config_file: None, overrides: Vec::new() };
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        let mut value = |flag: &str| args.next().cloned().ok_or(format!("The {} option needs a value", flag));
+
+        match arg.as_str() {
+            "--env" => cli.env_file = Some(value(arg)?),
+            "--config" => cli.config_file = Some(value(arg)?),
+            "--set" => {
+                let var = value(arg)?;
+                let (key, value) = var.split_once('=').ok_or(format!("The --set option needs a KEY=VALUE pair, got {}", var))?;
+                cli.overrides.push((key.trim().to_string(), value.to_string()));
+            },
+            "--format" => format = Some(match value(arg)?.as_str() {
+                "text" => OnceFormat::Text,
+                "json" => OnceFormat::Json,
+                other => return Err(format!("The --format option needs to be either text or json, got {}", other)),
+            }),
+            "-h" | "--help" | "help" => return Ok(Cli { command: Command::Help, ..cli }),
+            option if option.starts_with('-') => return Err(format!("Unknown option {}", option)),
+
+            _ if command.is_some() => return Err(format!("Only one command can be used, got {} as well", arg)),
+            "run" => command = Some(Command::Run),
+            "check" => command = Some(Command::Check),
+            "once" => command = Some(Command::Once(OnceFormat::Text)),
+            "print-config" => command = Some(Command::PrintConfig),
+            _ => return Err(format!("Unknown command {}", arg)),
+        }
+    }
+
+    cli.command = match (command.unwrap_or(Command::Run), format) {
+        (Command::Once(_), Some(format)) => Command::Once(format),
+        (_, Some(_)) => return Err("The --format option can only be used with the once command".to_string()),
+        (command, None) => command,
+    };
+
+    Ok(cli)
+}
+
+
+// Load the .env file, the config file and the --set variables, which every command reads the config from.
+// Returns the files, which the run command reads again when the config is reloaded.
+pub fn load_vars(cli: &Cli) -> ConfigSources {
+    let mut sources = ConfigSources::new(env_file(cli), cli.config_file.clone());
+
+    if let Err(err) = sources.load() {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+
+    parse_config::set_override_vars(cli.overrides.clone());
+    sources
+}
+
+
+// The .env file to read. Without an --env file, ./.env is only used if there is one. A config file is used on its own,
+// so a leftover ./.env doesn't override the file which was asked for.
+fn env_file(cli: &Cli) -> Option<String> {
+    match (&cli.env_file, &cli.config_file) {
+        (Some(env_file), _) => Some(env_file.clone()),
+        (None, Some(_)) => None,
+        (None, None) => Some("./.env".to_string()).filter(|path| std::path::Path::new(path).is_file()),
+    }
+}
+
+
+// Check the config, returning the exit code. Every problem is listed, so they can all be fixed at once.
+pub fn check() -> i32 {
+    match parse_config::create_config() {
+        Ok(config) => {
+            let modes = config.modes.iter().map(|(mode, _)| mode_name(mode)).collect::<Vec<&str>>();
+            let logs = config.logs.iter().map(|(_, credentials)| credentials.to_string()).collect::<Vec<String>>();
+            println!("The config is valid, watching with the {} mode and logging to {}", modes.join(" and "), logs.join(" and "));
+            0
+        },
+        Err(errors) => {
+            eprintln!("{}", parse_config::error_report(&errors));
+            1
+        },
+    }
+}
+
+
+// Take a single sample of every mode and print it. The system is refreshed twice a second apart, since the CPU
+// usage, network bandwidth and disk I/O are measured between two refreshes. A single sample can't wait for
+// the warn_for variables, so every limit which is surpassed right now is printed.
+pub fn once(config: &Config, once_format: OnceFormat) {
+    let mut system = System::new_all();
+    let mut interval_metrics = None;
+    let mut warn_metrics = None;
+
+    for (mode, _) in config.modes.iter() {
+        match mode {
+            ConfigMode::ConfigInterval { .. } => interval_metrics = Some(IntervalMetrics::new(config, &system)),
+            ConfigMode::ConfigWarn { .. } => warn_metrics = Some(WarnMetrics::single_sample(config)),
+        }
+    }
+
+    std::thread::sleep(Duration::from_secs(1));
+    system.refresh_all();
+
+    let hostname = system.get_host_name().unwrap_or_else(|| "unknown".into());
+    let sample = Sample::new(&system, &hostname, config.instance_name.as_deref());
+
+    if let Some(metrics) = &mut interval_metrics {
+        metrics.update_metrics(&system);
+
+        match once_format {
+            OnceFormat::Text => print!("{}", file::text_record(&sample, file::format_interval_metrics_text(metrics, &system))),
+            OnceFormat::Json => println!("{}", format::record_json(&sample, "interval", "metrics", format::interval_json(metrics, &system))),
+        }
+    }
+
+    if let Some(metrics) = &mut warn_metrics {
+        metrics.update_warns(&system);
+
+        match once_format {
+            OnceFormat::Text if metrics.alerts.is_empty() => print!("{}", file::text_record(&sample, "No limit is surpassed".to_string())),
+            OnceFormat::Text => print!("{}", file::text_record(&sample, file::format_warn_metrics_text(metrics, &system))),
+            OnceFormat::Json => println!("{}", format::record_json(&sample, "warn", "alerts", format::alert_json(&metrics.alerts))),
+        }
+    }
+}
+
+
+// The config with every default filled in, as it would be used. The credentials mask their secrets, so it can be shared.
+pub fn print_config(config: &Config) {
+    println!("{:#?}", config);
+}
+
+
+fn mode_name(mode: &ConfigMode) -> &'static str {
+    match mode {
+        ConfigMode::ConfigInterval { .. } => "interval",
+        ConfigMode::ConfigWarn { .. } => "warn",
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Command, OnceFormat, parse_args, env_file};
+
+
+    fn args(args: &str) -> Vec<String> {
+        args.split_whitespace().map(String::from).collect()
+    }
+
+
+    #[test]
+    fn parse_args_defaults_to_run() {
+        assert_eq!(parse_args(&[]), Ok(Cli { command: Command::Run, env_file: None, config_file: None, overrides: vec![] }));
+    }
+
+
+    #[test]
+    fn parse_args_parses_options() {
+        let cli = parse_args(&args("--env /etc/watcher.env once --set ram_limit=90 --format json --config watcher.toml --set mode=warn"));
+
+        assert_eq!(cli, Ok(Cli {
+            command: Command::Once(OnceFormat::Json),
+            env_file: Some("/etc/watcher.env".into()),
+            config_file: Some("watcher.toml".into()),
+            overrides: vec![("ram_limit".into(), "90".into()), ("mode".into(), "warn".into())],
+        }));
+    }
+
+
+    #[test]
+    fn parse_args_rejects_wrong_args() {
+        assert_eq!(parse_args(&args("start")), Err("Unknown command start".into()));
+        assert_eq!(parse_args(&args("check once")), Err("Only one command can be used, got once as well".into()));
+        assert_eq!(parse_args(&args("check --format json")), Err("The --format option can only be used with the once command".into()));
+        assert_eq!(parse_args(&args("--set ram_limit")), Err("The --set option needs a KEY=VALUE pair, got ram_limit".into()));
+        assert_eq!(parse_args(&args("run --config")), Err("The --config option needs a value".into()));
+        assert_eq!(parse_args(&args("--verbose")), Err("Unknown option --verbose".into()));
+    }
+
+
+    #[test]
+    fn env_file_skips_default_with_config() {
+        let with_config = parse_args(&args("--config watcher.toml")).unwrap();
+        let with_both = parse_args(&args("--config watcher.toml --env /etc/watcher.env")).unwrap();
+
+        assert_eq!(env_file(&with_config), None);
+        assert_eq!(env_file(&with_both), Some("/etc/watcher.env".into()));
+    }
+}
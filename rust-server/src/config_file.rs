@@ -0,0 +1,293 @@
+use serde_json::{Map, Value};
+
+
+// The sections of the logging methods, each key is prefixed with the section name (ex: discord.key to discord_key)
+const SINK_SECTIONS: [&str; 5] = ["discord", "file", "webhook", "slack", "prometheus"];
+
+
+// Read a .env file, with a key=value variable on each line. A # at the start of a line or after a space starts a
+// comment, so a value can still contain one (ex: a token or a URL fragment).
+pub fn read_env_file(path: &str) -> Result<Vec<(String, String)>, String> {
+    let text = std::fs::read_to_string(path).map_err(|_| format!("Couldn't read the {} env file", path))?;
+
+    text
+    .lines()
+    .enumerate()
+    .map(|(index, line)| (index, strip_comment(line)))
+    .filter(|(_, line)| !line.trim().is_empty())
+    .map(|(index, line)| {
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| format!("Line {} of the {} env file needs to be in the key=value format", index + 1, path))?;
+
+        Ok((key.trim().to_string(), value.trim().to_string()))
+    })
+    .collect()
+}
+
+
+fn strip_comment(line: &str) -> &str {
+    let comment = line
+        .char_indices()
+        .find(|&(index, char)| char == '#' && (index == 0 || line[..index].ends_with(char::is_whitespace)));
+
+    match comment {
+        Some((index, _)) => &line[..index],
+        None => line,
+    }
+}
+
+
+// Read a TOML or YAML config file (picked by its extension) and turn it into the same variables as the .env file.
+pub fn read_config_file(path: &str) -> Result<Vec<(String, String)>, String> {
+    let text = std::fs::read_to_string(path).map_err(|_| format!("Couldn't read the {} config file", path))?;
+
+    let value = if path.ends_with(".toml") {
+        toml::from_str::<Value>(&text).map_err(|err| format!("Couldn't parse the {} config file: {}", path, err))?
+    } else if path.ends_with(".yaml") || path.ends_with(".yml") {
+        serde_yaml::from_str::<Value>(&text).map_err(|err| format!("Couldn't parse the {} config file: {}", path, err))?
+    } else {
+        return Err(format!("The {} config file needs to end with .toml, .yaml or .yml", path))
+    };
+
+    match value {
+        Value::Object(sections) => flatten(&sections),
+        _ => Err(format!("The {} config file needs to be a table of keys and sections", path)),
+    }
+}
+
+
+// Turn each key of the file into its variable. The keys outside of a section are used as they are, so every .env
+// variable can be used in the file. Without a type key, every logging method with a section is used.
+fn flatten(sections: &Map<String, Value>) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+
+    for (name, value) in sections {
+        let section = match value {
+            Value::Object(section) => section,
+            _ => {
+                vars.push((name.clone(), text(name, value)?));
+                continue;
+            },
+        };
+
+        for (key, value) in section {
+            match (name.as_str(), key.as_str(), value) {
+                ("interval", "period", _) | ("warn", "period", _) => vars.push((format!("{}_period", name), text(key, value)?)),
+                ("interval", _, _) => vars.push((key.clone(), text(key, value)?)),
+
+                ("warn", "for", _) | ("warn", "reminder", _) => vars.push((format!("warn_{}", key), text(key, value)?)),
+                ("warn", "disk", Value::Object(disk)) => vars.extend(disk_vars(disk)?),
+                ("warn", metric, Value::Object(options)) => {
+                    for (option, value) in options {
+                        vars.push((format!("{}_{}", metric, option), text(option, value)?));
+                    }
+                },
+                ("warn", _, _) => vars.push((key.clone(), text(key, value)?)),
+
+                ("processes", process, _) => vars.push((format!("process.{}", process), text(key, value)?)),
+                ("disks", _, _) => vars.push((format!("disk_{}", key), text(key, value)?)),
+                ("network", _, _) => vars.push((format!("network_{}", key), text(key, value)?)),
+
+                ("file", "directory", _) => vars.push(("logging_directory".to_string(), text(key, value)?)),
+                ("webhook", "headers", Value::Object(headers)) => vars.push(("webhook_headers".to_string(), header_list(headers)?)),
+                (sink, _, _) if SINK_SECTIONS.contains(&sink) => vars.push((format!("{}_{}", sink, key), text(key, value)?)),
+
+                _ => return Err(format!("Unknown [{}] section in the config file", name)),
+            }
+        }
+    }
+
+    if !sections.contains_key("type") {
+        let log_types = SINK_SECTIONS
+            .iter()
+            .filter(|sink| sections.contains_key(**sink))
+            .copied()
+            .collect::<Vec<&str>>();
+
+        if !log_types.is_empty() { vars.push(("type".to_string(), log_types.join(","))) }
+    }
+
+    Ok(vars)
+}
+
+
+// The [warn.disk] section can have its own limit for each mount point, in a mounts table (ex: "/var" = 90).
+fn disk_vars(disk: &Map<String, Value>) -> Result<Vec<(String, String)>, String> {
+    let mut vars = Vec::new();
+
+    for (option, value) in disk {
+        match value {
+            Value::Object(mounts) if option == "mounts" => {
+                for (mount, limit) in mounts {
+                    vars.push((format!("disk_limit.{}", mount), text(mount, limit)?));
+                }
+            },
+            _ => vars.push((format!("disk_{}", option), text(option, value)?)),
+        }
+    }
+
+    Ok(vars)
+}
+
+
+// A headers table (ex: Authorization = "Bearer token") in the same Name: Value list as the webhook_headers variable.
+fn header_list(headers: &Map<String, Value>) -> Result<String, String> {
+    let headers = headers
+        .iter()
+        .map(|(name, value)| Ok(format!("{}: {}", name, text(name, value)?)))
+        .collect::<Result<Vec<String>, String>>()?;
+
+    Ok(headers.join(", "))
+}
+
+
+// A value as the text it would have in the .env file, a list is comma separated (ex: mode = ["warn", "interval"]).
+fn text(key: &str, value: &Value) -> Result<String, String> {
+    match value {
+        Value::String(text) => Ok(text.clone()),
+        Value::Number(number) => Ok(number.to_string()),
+        Value::Bool(boolean) => Ok(boolean.to_string()),
+        Value::Array(list) => {
+            let items = list.iter().map(|item| text(key, item)).collect::<Result<Vec<String>, String>>()?;
+            Ok(items.join(","))
+        },
+        Value::Null | Value::Object(_) => Err(format!("The {} key in the config file needs to be a value or a list", key)),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use super::{read_config_file, read_env_file};
+
+
+    fn var<'a>(vars: &'a [(String, String)], key: &str) -> Option<&'a str> {
+        vars.iter().find(|(var_key, _)| var_key == key).map(|(_, value)| value.as_str())
+    }
+
+
+    #[test]
+    fn read_config_file_flattens_toml() {
+        // Careful when editing this test to not accidently delete your files.
+        let dir = std::env::current_dir().unwrap().join("TEST_CONFIG_TOML");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("watcher.toml");
+        fs::write(&path, r#"
+mode = ["interval", "warn"]
+instance_name = "web-1"
+
+[interval]
+period = 60
+ram = true
+
+[warn]
+period = 30
+for = "5m"
+top_processes = 3
+
+[warn.ram]
+limit = 80.5
+critical = 95
+free_below = "512MB"
+
+[warn.disk]
+limit = 85
+mounts = { "/var" = 90 }
+
+[processes]
+db = "pidfile:/run/postgres.pid"
+
+[discord]
+key = "secret"
+channel = 123
+
+[webhook]
+url = "https://alerts.example.com/hook"
+headers = { Authorization = "Bearer token" }
+"#).unwrap();
+
+        let vars = read_config_file(path.to_str().unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(var(&vars, "mode"), Some("interval,warn"));
+        assert_eq!(var(&vars, "instance_name"), Some("web-1"));
+        assert_eq!(var(&vars, "interval_period"), Some("60"));
+        assert_eq!(var(&vars, "ram"), Some("true"));
+        assert_eq!(var(&vars, "warn_period"), Some("30"));
+        assert_eq!(var(&vars, "warn_for"), Some("5m"));
+        assert_eq!(var(&vars, "top_processes"), Some("3"));
+        assert_eq!(var(&vars, "ram_limit"), Some("80.5"));
+        assert_eq!(var(&vars, "ram_critical"), Some("95"));
+        assert_eq!(var(&vars, "ram_free_below"), Some("512MB"));
+        assert_eq!(var(&vars, "disk_limit"), Some("85"));
+        assert_eq!(var(&vars, "disk_limit./var"), Some("90"));
+        assert_eq!(var(&vars, "process.db"), Some("pidfile:/run/postgres.pid"));
+        assert_eq!(var(&vars, "discord_key"), Some("secret"));
+        assert_eq!(var(&vars, "discord_channel"), Some("123"));
+        assert_eq!(var(&vars, "webhook_headers"), Some("Authorization: Bearer token"));
+        assert_eq!(var(&vars, "type"), Some("discord,webhook"));
+    }
+
+
+    #[test]
+    fn read_config_file_flattens_yaml() {
+        // Careful when editing this test to not accidently delete your files.
+        let dir = std::env::current_dir().unwrap().join("TEST_CONFIG_YAML");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("watcher.yaml");
+        fs::write(&path, "mode: warn\ntype: file\nwarn:\n  cpu:\n    limit: 90\n    for: 3\nfile:\n  directory: /var/log/watcher\n  format: jsonl\n").unwrap();
+
+        let vars = read_config_file(path.to_str().unwrap()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(vars, vec![
+            ("logging_directory".to_string(), "/var/log/watcher".to_string()),
+            ("file_format".to_string(), "jsonl".to_string()),
+            ("mode".to_string(), "warn".to_string()),
+            ("type".to_string(), "file".to_string()),
+            ("cpu_for".to_string(), "3".to_string()),
+            ("cpu_limit".to_string(), "90".to_string()),
+        ]);
+    }
+
+
+    #[test]
+    fn read_config_file_rejects_unknown_section() {
+        // Careful when editing this test to not accidently delete your files.
+        let dir = std::env::current_dir().unwrap().join("TEST_CONFIG_UNKNOWN");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("watcher.toml");
+        fs::write(&path, "[metrics]\nram = true\n").unwrap();
+
+        let result = read_config_file(path.to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(result, Err("Unknown [metrics] section in the config file".to_string()));
+    }
+
+
+    #[test]
+    fn read_env_file_reads_vars() {
+        // Careful when editing this test to not accidently delete your files.
+        let dir = std::env::current_dir().unwrap().join("TEST_ENV_FILE");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".env");
+        fs::write(&path, "# Warn mode\nmode=warn\n\nram_limit=80 # percent\nwebhook_headers=Authorization: Bearer a=b\n  # Secrets\ndiscord_key=abc#123 # bot token\nwebhook_url=https://example.com/hook#prod\n").unwrap();
+        let vars = read_env_file(path.to_str().unwrap());
+
+        fs::write(&path, "mode=warn\nram_limit\n").unwrap();
+        let wrong_vars = read_env_file(path.to_str().unwrap());
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(vars, Ok(vec![
+            ("mode".to_string(), "warn".to_string()),
+            ("ram_limit".to_string(), "80".to_string()),
+            ("webhook_headers".to_string(), "Authorization: Bearer a=b".to_string()),
+            ("discord_key".to_string(), "abc#123".to_string()),
+            ("webhook_url".to_string(), "https://example.com/hook#prod".to_string()),
+        ]));
+        assert_eq!(wrong_vars, Err(format!("Line 2 of the {} env file needs to be in the key=value format", path.to_str().unwrap())));
+    }
+}
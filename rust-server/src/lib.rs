@@ -1,19 +1,52 @@
 pub mod parse_config;
+pub mod config_file;
+pub mod cli;
 pub mod logging;
 pub mod metrics;
+pub mod scheduler;
+pub mod reload;
+
+#[cfg(test)]
+mod test_env;
+
+pub use logging::{Sink, Sample};
+pub use reload::ConfigSources;
+
+
+// Parse the config, or list every problem it has and exit.
+pub fn config_or_exit() -> parse_config::Config {
+    match parse_config::create_config() {
+        Ok(config) => config,
+        Err(errors) => {
+            eprintln!("{}", parse_config::error_report(&errors));
+            std::process::exit(1);
+        },
+    }
+}
 
-use crate::parse_config::{ConfigMode};
 
 pub fn start() {
-    let config = parse_config::create_config();
+    start_with_sinks(Vec::new());
+}
+
+
+// Start watching with the sinks from the config, together with any extra sinks passed by the caller.
+// This lets library users log metrics to their own destinations by implementing `Sink`.
+pub fn start_with_sinks(extra_sinks: Vec<Box<dyn Sink>>) {
+    run(ConfigSources::default(), extra_sinks);
+}
+
 
-    match config.mode {
-        ConfigMode::ConfigInterval { ram: _, cpu: _, cpu_average: _, swap: _, system_uptime: _, disk: _} => {
-            logging::discord::start(config);
-        }
+// Start watching, reading the config again from its files whenever it is reloaded.
+#[tokio::main]
+pub async fn run(sources: ConfigSources, extra_sinks: Vec<Box<dyn Sink>>) {
+    let config = config_or_exit();
 
-        ConfigMode::ConfigWarn { cpu_limit: _, ram_limit: _, disk_limit: _, swap_limit: _ } => {
-            logging::file::start(config)
-        }
+    match scheduler::Scheduler::new(&config, extra_sinks) {
+        Ok(scheduler) => scheduler.run(sources).await,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        },
     }
-}
\ No newline at end of file
+}
@@ -6,167 +6,121 @@ use serenity::{
 
 
 use super::super::metrics::{
+    alert::Alert,
     interval::IntervalMetrics,
-    warn::{WarnMetrics, Warn},
+    warn::{Severity, WarnMetrics},
 };
 
 
-use sysinfo::{System, SystemExt};
-use super::super::parse_config::{Config, ConfigMode};
+use async_trait::async_trait;
+use sysinfo::System;
+use super::super::parse_config::{LogCredentials};
+use super::{Sink, Sample, format, format::AlertGroup};
 
 
-use tokio::{self};
 mod parsers;
 
 
-#[tokio::main]
-pub async fn start(config: Config) {
-    let (token, channel) = parsers::parse_token_and_channel(&config);
-    let system = System::new_all();
-    let discord_connection = http::Http::new_with_token(&token);
-    let channel_connection = ChannelId(channel);
-
-
-    match config.mode {
-        ConfigMode::ConfigInterval {
-            ram: _ram,
-            cpu: _cpu,
-            cpu_average: _cpu_average,
-            system_uptime: _system_uptime,
-            disk: _disk,
-            swap: _swap,
-        } => {
-            send_interval_message(
-          IntervalMetrics::new(&config, &system),
-                 system,
-                 discord_connection, 
-   channel_connection, 
-                 config
-            )
-            .await
-        },
-        ConfigMode::ConfigWarn {
-            cpu_limit: _cpu_limit,
-            ram_limit: _ram_limit,
-            disk_limit: _disk_limit,
-            swap_limit: _swap_limit
-        } => {
-            send_warn_message(
-        WarnMetrics::new(&config), 
-                system, 
-                discord_connection, 
-  channel_connection, 
-                config
-            )
-            .await
-        }
-    };
+pub struct DiscordSink {
+    discord_connection: Http,
+    discord_channel: ChannelId,
+
+    // Where the critical alerts are sent instead, if set
+    critical_channel: Option<ChannelId>,
 }
 
 
+impl DiscordSink {
+    pub fn new(log_credentials: &LogCredentials) -> DiscordSink {
+        let (token, channel, critical_channel) = parsers::parse_token_and_channel(log_credentials);
+
+        DiscordSink {
+            discord_connection: http::Http::new_with_token(&token),
+            discord_channel: ChannelId(channel),
+            critical_channel: critical_channel.map(ChannelId),
+        }
+    }
+}
 
-async fn send_interval_message(
-    mut metrics: IntervalMetrics,
-    mut system: System, 
-    discord_connection: Http, 
-    discord_channel: ChannelId,
-    config: Config,
-) {
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(config.interval as u64));
-        metrics.update_metrics(&mut system);
 
-        discord_channel.send_message(&discord_connection, |msg| {
+#[async_trait]
+impl Sink for DiscordSink {
+    async fn emit_interval(&mut self, metrics: &IntervalMetrics, sample: &Sample<'_>) {
+        let message = self.discord_channel.send_message(&self.discord_connection, |msg| {
             msg.embed(|emb| {
                 emb.title("Server Interval Metrics");
                 emb.color((0, 190, 219));
-                load_interval_embed(emb, &metrics, &system);
+                load_interval_embed(emb, metrics, sample.system);
+                emb.footer(|footer| footer.text(format::identity(sample)));
                 emb
             });
 
             msg
         })
-        .await
-        .expect("Couldn't send a message to this channel");
-    }
-}
-
-
-// Check each interval metric. If it's above -1 (enabled), append in to the embed.
-fn load_interval_embed(embed: &mut CreateEmbed, metrics: &IntervalMetrics, system: &System) {
-    if metrics.ram.is_some() {
-        embed.field("Used RAM", format!("{} MB out of {} MB", metrics.ram.unwrap() / 1000, system.get_total_memory() / 1000), false);
-    };
+        .await;
 
-    if metrics.swap.is_some() {
-        embed.field("Used Swap", format!("{} MB out of {} MB", metrics.swap.unwrap() / 1000, system.get_total_swap() / 1000), false);
+        // A failed message is only logged, so the other sinks still get the metrics.
+        if let Err(err) = message {
+            eprintln!("Couldn't send the interval metrics to Discord: {}", err);
+        }
     }
 
-    if metrics.cpu.is_some() {
-        embed.field("Used CPU", format!("{:.2}%", metrics.cpu.unwrap()), false);
-    }
 
-    if metrics.system_uptime.is_some() {
-        embed.field("System Uptime", format!("{} minutes", metrics.system_uptime.unwrap() / 60), false);
+    // The firing critical alerts, the firing warnings and the resolved alerts are sent as separate red, orange
+    // and green messages. The critical ones go to the critical channel if there is one.
+    async fn emit_warn(&mut self, metrics: &WarnMetrics, sample: &Sample<'_>) {
+        for (group, alerts) in format::group_alerts(&metrics.alerts) {
+            let channel = match (group.severity(), self.critical_channel) {
+                (Severity::Critical, Some(critical_channel)) => critical_channel,
+                _ => self.discord_channel,
+            };
+
+            let message = channel.send_message(&self.discord_connection, |msg| {
+                msg.embed(|emb| {
+                    emb.title(group.title());
+                    emb.color(group_color(group));
+                    load_warn_embed(emb, &alerts, sample.system);
+                    emb.footer(|footer| footer.text(format::identity(sample)));
+                    emb
+                });
+
+                msg
+            })
+            .await;
+
+            if let Err(err) = message {
+                eprintln!("Couldn't send the warn metrics to Discord: {}", err);
+            }
+        }
     }
+}
 
-    if metrics.disk.is_some() {
-        embed.field("Used System Space:", format!("{} MB", metrics.disk.unwrap()), false);
-    }
 
-    if metrics.cpu_average.is_some() {
-        let cpu_avg = metrics.cpu_average.unwrap();
-
-        embed.field(
-            "Average Load",
-            format!(
-                    "One minute: {:.2}%, Five minutes: {:.2}%, Fiveteen minutes: {:.2}%", 
-                    cpu_avg.0, cpu_avg.1, cpu_avg.2
-                  ),
-                 false
-        );
-    }
+// Append each enabled interval metric to the embed as a separate field.
+fn load_interval_embed(embed: &mut CreateEmbed, metrics: &IntervalMetrics, system: &System) {
+    embed.fields(
+        format::interval_fields(metrics, system)
+        .into_iter()
+        .map(|(name, value)| (name, value, false))
+    );
 }
 
 
-async fn send_warn_message(
-    mut metrics: WarnMetrics,
-    mut system: System, 
-    discord_connection: Http,
-    discord_channel: ChannelId,
-    config: Config,
-) {
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(config.interval as u64));
-        metrics.update_warns(&mut system);
-        if metrics.warnings.len() < 1 { continue };
-
-        discord_channel.send_message(&discord_connection, |msg| {
-            msg.embed(|emb| {
-                emb.title("Server Warn Metrics");
-                emb.color((197, 0, 0));
-                load_warn_embed(emb, &metrics, &system);
-                emb
-            });
-
-            msg
-        })
-        .await
-        .expect("Couldn't send a message to this channel");
+fn group_color(group: AlertGroup) -> (u8, u8, u8) {
+    match group {
+        AlertGroup::Firing(Severity::Critical) => (197, 0, 0),
+        AlertGroup::Firing(Severity::Warning) => (230, 126, 34),
+        AlertGroup::Resolved(_) => (0, 160, 70),
     }
 }
 
 
-// Check each warn metric, create a separate embed field for it and set it on the embed.
-fn load_warn_embed(embed: &mut CreateEmbed, metrics: &WarnMetrics, system: &System) {
+// Append each alert to the embed as a separate field.
+fn load_warn_embed(embed: &mut CreateEmbed, alerts: &[&Alert], system: &System) {
     embed.fields(
-        metrics.warnings.iter().map(|metric| {
-            match metric {
-                &Warn::HighCPU(cpu) => ("CPU Limit Surpassed", format!("{:.2}%", cpu), false),
-                &Warn::HighRAM(ram) => ("RAM Limit Surpassed", format!("{:.2}% out of {} MB", ram, system.get_total_memory() / 1000), false),
-                &Warn::HighDisk(disk) => ("Disk Limit Surpassed", format!("{:.2}%", disk), false),
-                &Warn::HighSwap(swap) => ("Swap Limit Surpassed", format!("{:.2}% out of {} MB", swap, system.get_total_swap() / 1000), false),
-            }
-        })
+        alerts
+        .iter()
+        .flat_map(|alert| format::alert_fields(alert, system))
+        .map(|(name, value)| (name, value, false))
     );
-}
\ No newline at end of file
+}
@@ -1,14 +1,9 @@
-use super::super::super::parse_config::{LogType, Config, LogCredentials, ConfigMode};
-
-pub fn parse_token_and_channel(config: &Config) -> (String, u64) {
-    let log_type = &config.log_type;
-    let log_credentials = &config.log_credentials;
-
-    if log_type != &LogType::Discord { panic!("The current logging mode is not set to discord") }
+use super::super::super::parse_config::LogCredentials;
 
+pub fn parse_token_and_channel(log_credentials: &LogCredentials) -> (String, u64, Option<u64>) {
     match log_credentials {
-        LogCredentials::DiscordLog { key, channel } => {
-            (key.clone(), channel.clone())
+        LogCredentials::DiscordLog { key, channel, critical_channel } => {
+            (key.clone(), *channel, *critical_channel)
         },
         _ => panic!("Wrong log credentials are being used")
     }
@@ -17,28 +12,34 @@ pub fn parse_token_and_channel(config: &Config) -> (String, u64) {
 
 #[cfg(test)]
 mod tests {
-    use super::{LogType, Config, LogCredentials, ConfigMode, parse_token_and_channel};
+    use super::{LogCredentials, parse_token_and_channel};
+    use super::super::super::super::parse_config::{FileFormat, FileRotation};
 
     #[test]
     fn parse_token_channel_parses() {
-        let dummy_config = Config {
-            mode: ConfigMode::ConfigWarn {
-                cpu_limit: 0,
-                ram_limit: 0,
-                disk_limit: 0,
-                swap_limit: 0,
-            },
-            interval: 0,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "test".to_string(),
-                channel: 098823098234,
-            }
+        let credentials = LogCredentials::DiscordLog {
+            key: "test".to_string(),
+            channel: 98823098234,
+            critical_channel: None,
         };
 
-        let (key, channel) = parse_token_and_channel(&dummy_config);
+        let (key, channel, critical_channel) = parse_token_and_channel(&credentials);
 
         assert_eq!(key, "test".to_string());
-        assert_eq!(channel, 098823098234);
+        assert_eq!(channel, 98823098234);
+        assert_eq!(critical_channel, None);
     }
-}
\ No newline at end of file
+
+
+    #[test]
+    #[should_panic = "Wrong log credentials are being used"]
+    fn parse_token_channel_wrong_credentials() {
+        let credentials = LogCredentials::FileLog {
+            path: "C:/special/path".into(),
+            format: FileFormat::Text,
+            rotation: FileRotation::default(),
+        };
+
+        parse_token_and_channel(&credentials);
+    }
+}
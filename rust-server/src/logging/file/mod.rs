@@ -1,254 +1,176 @@
-use crate::metrics::warn::Warn;
-use crate::parse_config::{ Config, ConfigMode };
+use crate::parse_config::{LogCredentials, FileFormat};
 use crate::metrics::{interval::IntervalMetrics, warn::WarnMetrics};
-use sysinfo::{self, System, SystemExt};
-use std::fs;
+use super::{Sink, Sample, format};
+use async_trait::async_trait;
+use sysinfo::System;
 use std::path::PathBuf;
-use std::io::Write;
 
 
 mod parsers;
+mod rotation;
 
+use rotation::RotatingWriter;
 
-pub fn start(config: Config) {
-    let directory = parsers::get_directory(&config);
-    let system = sysinfo::System::new_all();
-
-    match config.mode {
-        ConfigMode::ConfigInterval {
-            ram: _,
-            cpu: _,
-            cpu_average: _,
-            system_uptime: _,
-            disk: _,
-            swap: _
-        } => {
-            let metrics = IntervalMetrics::new(&config, &system);
-            log_interval(
-                directory,
-                system,
-                metrics, 
-                config
-            )
-        },
-
-        ConfigMode::ConfigWarn {
-            cpu_limit: _,
-            ram_limit: _,
-            disk_limit: _,
-            swap_limit: _,
-        } => {
-            let metrics = WarnMetrics::new(&config);
-            log_warn(
-                directory,
-                system,
-                metrics,
-                config
-            )
-        }
-    }
-}
 
+// Appends every sample to a log file in the logging directory. Text logs use interval.log and
+// warn.log, JSON Lines logs put both modes in metrics.jsonl. Each log file is rotated on its own.
+pub struct FileSink {
+    format: FileFormat,
+    interval_writer: RotatingWriter,
 
-fn log_interval(
-    dir: String,
-    mut system: System, 
-    mut metrics: IntervalMetrics,
-    config: Config,
-) {
-    let interval_path = PathBuf::from(dir).join("./interval");
-
-    // Create the logging path if it does not exist.
-    fs::create_dir_all(&interval_path).expect("Couldn't create the specified logging directory");
-    let base_file = "log";
-    
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(config.interval as u64));
-        metrics.update_metrics(&mut system);
-
-        let next_name = find_next_free_name(base_file, &interval_path.to_str().unwrap());
-        let metrics_formatted = format_interval_metrics_text(&metrics, &system);
-        let file_path = PathBuf::from(&interval_path).join(next_name);
-        let mut file = fs::File::create(file_path).expect("Couldn't create a file at the expected logging directory");
-
-        file.write_all(metrics_formatted.as_bytes()).expect("Couldn't write information to a logging file");
-    }
+    // None when both modes write to the same file, which then only has the interval writer
+    warn_writer: Option<RotatingWriter>,
 }
 
 
-// Loop through all the metrics, check if they are enabled and if they are combine
-// them into a string and return them
-fn format_interval_metrics_text(metrics: &IntervalMetrics, system: &System) -> String {
-    let mut metric_list = Vec::new();
+impl FileSink {
+    pub fn new(log_credentials: &LogCredentials) -> FileSink {
+        let (directory, format, rotation) = parsers::parse_file_credentials(log_credentials);
+        let directory = PathBuf::from(directory);
 
-    if metrics.ram.is_some() {
-        let message = format!("Used RAM: {} MB out of {} MB", metrics.ram.unwrap() / 1000, system.get_total_memory() / 1000);
-        metric_list.push(message)
-    }
-
-    if metrics.swap.is_some() {
-        let message = format!("Used Swap: {} MB out of {} MB", metrics.swap.unwrap() / 1000, system.get_total_swap() / 1000);
-        metric_list.push(message)
-    }
+        let (interval_writer, warn_writer) = match format {
+            FileFormat::Text => (
+                RotatingWriter::new(directory.join("interval.log"), rotation),
+                Some(RotatingWriter::new(directory.join("warn.log"), rotation)),
+            ),
 
-    if metrics.cpu.is_some() {
-        let message = format!("Used CPU: {:.2}%", metrics.cpu.unwrap());
-        metric_list.push(message); 
-    }
+            FileFormat::JsonLines => (RotatingWriter::new(directory.join("metrics.jsonl"), rotation), None),
+        };
 
-    if metrics.cpu_average.is_some() {
-        let (min, five_min, fteen_min) = metrics.cpu_average.unwrap();
-        let message = format!("CPU Average: 1 minute - {:.2}%, 5 minutes - {:.2}%, 15 minutes - {:.2}%", min, five_min, fteen_min);
-        metric_list.push(message)
+        FileSink {
+            format,
+            interval_writer,
+            warn_writer,
+        }
     }
 
-    if metrics.system_uptime.is_some() {
-        let message = format!("System Uptime: {} minutes", metrics.system_uptime.unwrap() / 1000);
-        metric_list.push(message)
-    }
 
-    if metrics.disk.is_some() {
-        let message = format!("Used Disk Space: {} MB", metrics.disk.unwrap() / 1000);
-        metric_list.push(message)
+    fn warn_writer(&mut self) -> &mut RotatingWriter {
+        match &mut self.warn_writer {
+            Some(warn_writer) => warn_writer,
+            None => &mut self.interval_writer,
+        }
     }
-
-    metric_list.join("\n")
 }
 
 
-fn log_warn(
-    dir: String, 
-    mut system: System, 
-    mut metrics: WarnMetrics,
-    config: Config,
-) {
-    let warn_path = PathBuf::from(dir).join("./warn");
-
-    // Create the logging path if it does not exist.
-    fs::create_dir_all(&warn_path).expect("Couldn't create the specified logging directory");
-    let base_file = "log";
+#[async_trait]
+impl Sink for FileSink {
+    async fn emit_interval(&mut self, metrics: &IntervalMetrics, sample: &Sample<'_>) {
+        let record = match self.format {
+            FileFormat::Text => text_record(sample, format_interval_metrics_text(metrics, sample.system)),
+            FileFormat::JsonLines => format!(
+                "{}\n",
+                format::record_json(sample, "interval", "metrics", format::interval_json(metrics, sample.system))
+            ),
+        };
 
-    loop {
-        std::thread::sleep(std::time::Duration::from_secs(config.interval as u64));
-        metrics.update_warns(&mut system);
+        if let Err(err) = self.interval_writer.write_record(&record) {
+            eprintln!("Couldn't write the interval metrics to {}: {}", self.interval_writer.path().display(), err);
+        }
+    }
 
 
-        let next_name = find_next_free_name(base_file, &warn_path.to_str().unwrap());
-        let metrics_formatted = format_warn_metrics_text(&metrics, &system);
-        let file_path = PathBuf::from(&warn_path).join(next_name);
+    async fn emit_warn(&mut self, metrics: &WarnMetrics, sample: &Sample<'_>) {
+        let record = match self.format {
+            FileFormat::Text => text_record(sample, format_warn_metrics_text(metrics, sample.system)),
+            FileFormat::JsonLines => format!(
+                "{}\n",
+                format::record_json(sample, "warn", "alerts", format::alert_json(&metrics.alerts))
+            ),
+        };
 
-        // If a warn happened, save it in a file.
-        if !metrics_formatted.trim().is_empty() {
-            let mut file = fs::File::create(file_path).expect("Couldn't create a file at the expected logging directory");
-            file.write_all(metrics_formatted.as_bytes()).expect("Couldn't write information to a logging file");
+        let warn_writer = self.warn_writer();
+        if let Err(err) = warn_writer.write_record(&record) {
+            eprintln!("Couldn't write the warn metrics to {}: {}", warn_writer.path().display(), err);
         }
     }
 }
 
 
-fn format_warn_metrics_text(metrics: &WarnMetrics, system: &System) -> String {
-    let mut warn_list = Vec::new();
-
-    metrics.warnings.iter().for_each(|warn| {
-        match *warn {
-            Warn::HighCPU(cpu) => {
-                let message = format!("High CPU Usage: {:.2}%", cpu);
-                warn_list.push(message)
-            },
-
-            Warn::HighRAM(ram) => {
-                let message = format!("High RAM Usage: {:.2}% out of {} MB", ram, system.get_total_memory() / 1000);
-                warn_list.push(message)
-            },
-
-
-            Warn::HighDisk(disk) => {
-                let message = format!("High Disk Space Usage: {:.2}%", disk);
-                warn_list.push(message)
-            }, 
-
-            Warn::HighSwap(swap) => {
-                let message = format!("High Swap Usage: {:.2}% out of {} MB", swap, system.get_total_swap() / 1000);
-                warn_list.push(message)
-            },
-        }
-    });
-
-    warn_list.join("\n")
+// Put a header line telling where and when the sample was taken above the metrics,
+// separating the records with an empty line.
+pub fn text_record(sample: &Sample, metrics_text: String) -> String {
+    format!("[{}]\n{}\n\n", format::identity(sample), metrics_text)
 }
 
 
-// Opens a directory and finds the next free level of the passed file name, extended with _n.
-// Ex: in a directory, there're files log_1, log_2, log_3. This function will return log_4.
-fn find_next_free_name(filename: &str, dir: &str) -> String {
-    let dir_items = fs::read_dir(dir).expect("Couldn't read the specified logging directory");
-    let mut new_name = String::from("");
-    let mut num_of_files = 1;
-    let mut dir_files = std::collections::HashMap::new();
+// Put each enabled metric on its own line.
+pub fn format_interval_metrics_text(metrics: &IntervalMetrics, system: &System) -> String {
+    format::interval_fields(metrics, system)
+    .iter()
+    .map(|(name, value)| text_line(name, value))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
 
 
-    // Save each file name into a hashmap which will later be queried
-    dir_items.for_each(|file| {
-        let file_from_dir = file.unwrap().file_name().to_str().unwrap().to_string();
-        dir_files.insert(file_from_dir, 1);
-    });
+// Put each alert on its own line, starting with its severity and state (ex: "CRITICAL FIRING High RAM Usage: ..."),
+// so the log records when each warning started and resolved.
+pub fn format_warn_metrics_text(metrics: &WarnMetrics, system: &System) -> String {
+    metrics.alerts
+    .iter()
+    .flat_map(|alert| {
+        let prefix = format!("{} {}", alert.warn.severity().name(), alert.state.name()).to_uppercase();
+
+        format::alert_fields(alert, system)
+        .into_iter()
+        .map(move |(name, value)| text_line(&format!("{} {}", prefix, name), &value))
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
 
-    // Loop and increment the name until a non taken file name spot is found
-    loop {
-        new_name = format!("{}_{}.txt", filename, num_of_files);
-        if dir_files.get(&new_name).is_none() { break }
 
-        num_of_files += 1;
+// Values spanning more than a line (ex: the top processes) continue indented, under their name.
+fn text_line(name: &str, value: &str) -> String {
+    if value.contains('\n') {
+        format!("{}:\n    {}", name, value.replace('\n', "\n    "))
+    } else {
+        format!("{}: {}", name, value)
     }
-
-    new_name
 }
 
 
 #[cfg(test)]
 mod tests {
     use std::{fs};
+    use std::time::Duration;
     use sysinfo::{SystemExt, System};
-    use crate::metrics::{interval::IntervalMetrics, warn::WarnMetrics, warn::Warn};
-    use crate::parse_config::{Config, ConfigMode, LogType, LogCredentials};
-
-    use super::{find_next_free_name, format_interval_metrics_text, format_warn_metrics_text};
-
-
-    #[test]
-    fn find_next_free_name_finds_a_name() {
-        let dir = std::env::current_dir().unwrap().join("TEST");
-
-        fs::create_dir(&dir).unwrap();
-        fs::File::create("./TEST/log_2.txt").unwrap();
-        fs::File::create("./TEST/log_1.txt").unwrap();
-
-        let name = find_next_free_name("log", dir.to_str().unwrap());
-        
-        // Careful when editing this test to not accidently delete your files.
-        fs::remove_dir_all(&dir).unwrap();
-        
+    use crate::metrics::{alert::{Alert, AlertState}, interval::IntervalMetrics, warn::{Limit, Severity, WarnMetrics, Warn}};
+    use crate::parse_config::{Config, ConfigMode, LogType, LogCredentials, FileFormat, FileRotation};
+    use serde_json::Value;
 
-        assert_eq!(name, "log_3.txt")
-    }
+    use crate::logging::{Sink, Sample};
+    use super::{FileSink, format_interval_metrics_text, format_warn_metrics_text, text_record, text_line};
 
 
     #[test]
     fn format_interval_metrics_text_formats_some() {
         let config = Config {
-            mode: ConfigMode::ConfigInterval {
-                ram: true,
-                cpu: true,
-                cpu_average: false,
-                system_uptime: false,
-                disk: false,
-                swap: true,
-            },
-            interval: 10,
-            log_type: LogType::File,
-            log_credentials: LogCredentials::FileLog {
-                path: "C:/random/path".into()
-            }
+            modes: vec![
+                (ConfigMode::ConfigInterval {
+                    ram: true,
+                    cpu: true,
+                    cpu_cores: false,
+                    cpu_average: false,
+                    system_uptime: false,
+                    disk: false,
+                    swap: true,
+                    processes: false,
+                    network: false,
+                    disk_io: false,
+                    temperature: false,
+                }, 10)
+            ],
+            logs: vec![
+                (LogType::File, LogCredentials::FileLog {
+                    path: "C:/random/path".into(),
+                    format: FileFormat::Text,
+                    rotation: FileRotation::default(),
+                })
+            ],
+            ..Config::default()
         };
         let system = System::new_all();
         let metrics = IntervalMetrics::new(&config, &system);
@@ -256,13 +178,13 @@ mod tests {
         let message = format_interval_metrics_text(&metrics, &system);
 
         let separated_metrics = message.lines().collect::<Vec<&str>>();
-        let metric_messages = vec!["Used RAM:", "Used Swap:", "Used CPU:"];
+        let metric_messages = ["Used RAM:", "Used Swap:", "Used CPU:"];
 
         assert_eq!(separated_metrics.len(), 3);
 
         for (ind, &value) in separated_metrics.iter().enumerate() {
             println!("{} {}", value, metric_messages[ind]);
-            assert_eq!(value.starts_with(metric_messages[ind]), true);
+            assert!(value.starts_with(metric_messages[ind]));
         }
     }
 
@@ -270,19 +192,29 @@ mod tests {
     #[test]
     fn format_interval_metrics_text_formats_all() {
         let config = Config {
-            mode: ConfigMode::ConfigInterval {
-                ram: true,
-                cpu: true,
-                cpu_average: true,
-                system_uptime: true,
-                disk: true,
-                swap: true,
-            },
-            interval: 10,
-            log_type: LogType::File,
-            log_credentials: LogCredentials::FileLog {
-                path: "C:/random/path".into()
-            }
+            modes: vec![
+                (ConfigMode::ConfigInterval {
+                    ram: true,
+                    cpu: true,
+                    cpu_cores: false,
+                    cpu_average: true,
+                    system_uptime: true,
+                    disk: true,
+                    swap: true,
+                    processes: false,
+                    network: false,
+                    disk_io: false,
+                    temperature: false,
+                }, 10)
+            ],
+            logs: vec![
+                (LogType::File, LogCredentials::FileLog {
+                    path: "C:/random/path".into(),
+                    format: FileFormat::Text,
+                    rotation: FileRotation::default(),
+                })
+            ],
+            ..Config::default()
         };
         let system = System::new_all();
         let metrics = IntervalMetrics::new(&config, &system);
@@ -290,13 +222,13 @@ mod tests {
         let message = format_interval_metrics_text(&metrics, &system);
 
         let separated_metrics = message.lines().collect::<Vec<&str>>();
-        let metric_messages = vec!["Used RAM:", "Used Swap:", "Used CPU:", 
+        let metric_messages = ["Used RAM:", "Used Swap:", "Used CPU:", 
                                             "CPU Average:", "System Uptime:", "Used Disk Space:"];
 
         assert_eq!(separated_metrics.len(), 6);
 
         for (ind, &value) in separated_metrics.iter().enumerate() {
-            assert_eq!(value.starts_with(metric_messages[ind]), true);
+            assert!(value.starts_with(metric_messages[ind]));
         }
     }
 
@@ -305,33 +237,109 @@ mod tests {
     fn format_warn_metrics_text_formats_some() {
         let system = System::new_all();
         let config = Config {
-            mode: ConfigMode::ConfigWarn {
-                ram_limit: 40,
-                cpu_limit: 45,
-                disk_limit: 50,
-                swap_limit: 34,
-            },
-            interval: 10,
-            log_type: LogType::File,
-            log_credentials: LogCredentials::FileLog {
-                path: "C:/random/path".into()
-            }
+            modes: vec![
+                (ConfigMode::warn_limits(40.0, 45.0, 50.0, 34.0), 10)
+            ],
+            logs: vec![
+                (LogType::File, LogCredentials::FileLog {
+                    path: "C:/random/path".into(),
+                    format: FileFormat::Text,
+                    rotation: FileRotation::default(),
+                })
+            ],
+            ..Config::default()
         };
 
         let mut metrics = WarnMetrics::new(&config);
-        metrics.warnings = vec![
-            Warn::HighRAM(50.0),
-            Warn::HighCPU(70.0),
+        metrics.alerts = vec![
+            Alert { state: AlertState::Firing, warn: Warn::HighRAM(Severity::Warning, 50.0, vec![]), duration: Duration::from_secs(0) },
+            Alert { state: AlertState::Resolved, warn: Warn::HighCPU(Severity::Critical, 70.0, vec![]), duration: Duration::from_secs(120) },
         ];
 
         let message = format_warn_metrics_text(&metrics, &system);
         let separated_warns = message.lines().collect::<Vec<&str>>();
-        let metric_messages = vec!["High RAM Usage:", "High CPU Usage:"];
+        let metric_messages = ["WARNING FIRING High RAM Usage:", "CRITICAL RESOLVED High CPU Usage: Back to normal after 2 minutes"];
 
         assert_eq!(separated_warns.len(), 2);
 
         for (ind, &val) in separated_warns.iter().enumerate() {
-            assert_eq!(val.starts_with(metric_messages[ind]), true);
+            assert!(val.starts_with(metric_messages[ind]));
         }
     }
+
+
+    #[test]
+    fn text_line_indents_multiple_lines() {
+        assert_eq!(text_line("High CPU Usage", "70.00%"), "High CPU Usage: 70.00%");
+        assert_eq!(
+            text_line("Top CPU Processes", "1 init\n2 sshd"),
+            "Top CPU Processes:\n    1 init\n    2 sshd"
+        );
+    }
+
+
+    #[test]
+    fn text_record_adds_header() {
+        let system = System::new();
+        let sample = Sample::new(&system, "test-host", Some("web-1"));
+
+        let record = text_record(&sample, "Used CPU: 12.50%".into());
+        let lines = record.lines().collect::<Vec<&str>>();
+
+        assert!(lines[0].starts_with("[web-1 (test-host) - "));
+        assert!(lines[0].ends_with("Z]"));
+        assert_eq!(lines[1], "Used CPU: 12.50%");
+        assert!(record.ends_with("\n\n"));
+    }
+
+
+    #[tokio::test]
+    async fn file_sink_appends_both_modes() {
+        let dir = std::env::current_dir().unwrap().join("TEST_FILE_SINK");
+        fs::create_dir(&dir).unwrap();
+
+        let system = System::new();
+        let mut sink = FileSink::new(&LogCredentials::FileLog {
+            path: dir.to_str().unwrap().into(),
+            format: FileFormat::JsonLines,
+            rotation: FileRotation::default(),
+        });
+        let interval_metrics = IntervalMetrics {
+            cpu: Some(12.5),
+            ..IntervalMetrics::default()
+        };
+        let warn_metrics = WarnMetrics {
+            cpu: Limit::new(10.0, 0.0),
+            alerts: vec![Alert { state: AlertState::Firing, warn: Warn::HighCPU(Severity::Warning, 12.5, vec![]), duration: Duration::from_secs(0) }],
+            warnings: vec![Warn::HighCPU(Severity::Warning, 12.5, vec![])],
+            ..WarnMetrics::default()
+        };
+
+        let sample = Sample::new(&system, "test-host", None);
+
+        sink.emit_interval(&interval_metrics, &sample).await;
+        sink.emit_warn(&warn_metrics, &sample).await;
+        sink.emit_interval(&interval_metrics, &sample).await;
+
+        let text = fs::read_to_string(dir.join("metrics.jsonl")).unwrap();
+        let files = fs::read_dir(&dir).unwrap().count();
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        let records = text
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<Value>>();
+        let modes = records
+            .iter()
+            .map(|record| record["mode"].as_str().unwrap())
+            .collect::<Vec<&str>>();
+
+        assert_eq!(modes, vec!["interval", "warn", "interval"]);
+        assert_eq!(records[1]["hostname"], "test-host");
+        assert_eq!(records[1]["alerts"][0]["state"], "firing");
+        assert!(chrono::DateTime::parse_from_rfc3339(records[1]["timestamp"].as_str().unwrap()).is_ok());
+        assert_eq!(files, 1);
+    }
 }
@@ -1,15 +1,9 @@
-use crate::parse_config::{Config, LogType, LogCredentials};
+use crate::parse_config::{LogCredentials, FileFormat, FileRotation};
 
 
-pub fn get_directory(config: &Config) -> String {
-    match config.log_type {
-        LogType::File => {
-            match &config.log_credentials {
-                LogCredentials::FileLog { path } => path.into(),
-                _ => panic!("The logging mode is not set to file logging.")
-            }
-        },
-
+pub fn parse_file_credentials(log_credentials: &LogCredentials) -> (String, FileFormat, FileRotation) {
+    match log_credentials {
+        LogCredentials::FileLog { path, format, rotation } => (path.into(), *format, *rotation),
         _ => panic!("The logging mode is not set to file logging.")
     }
 }
@@ -17,50 +11,35 @@ pub fn get_directory(config: &Config) -> String {
 
 #[cfg(test)]
 mod tests {
-    use crate::parse_config::{Config, ConfigMode, LogType, LogCredentials};
-    use super::get_directory;
+    use crate::parse_config::{LogCredentials, FileFormat, FileRotation};
+    use super::parse_file_credentials;
 
 
     #[test]
-    fn get_directory_gets_dir() {
-        let config = Config {
-            mode: ConfigMode::ConfigWarn {
-                cpu_limit: 0,
-                ram_limit: 0,
-                swap_limit: 0,
-                disk_limit: 0,
-            },
-            interval: 10,
-            log_type: LogType::File,
-            log_credentials: LogCredentials::FileLog {
-                path: "C:/special/path".into()
-            }
+    fn parse_file_credentials_parses() {
+        let credentials = LogCredentials::FileLog {
+            path: "C:/special/path".into(),
+            format: FileFormat::JsonLines,
+            rotation: FileRotation::default(),
         };
 
-        let path = get_directory(&config);
+        let (path, format, rotation) = parse_file_credentials(&credentials);
 
         assert_eq!(path, "C:/special/path");
+        assert_eq!(format, FileFormat::JsonLines);
+        assert_eq!(rotation, FileRotation::default());
     }
 
 
     #[test]
     #[should_panic = "The logging mode is not set to file logging."]
-    fn get_directory_wrong_logging_mode() {
-        let config = Config {
-            mode: ConfigMode::ConfigWarn {
-                cpu_limit: 0,
-                ram_limit: 0,
-                swap_limit: 0,
-                disk_limit: 0,
-            },
-            interval: 10,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "asd".into(),
-                channel: 12345,
-            }
+    fn parse_file_credentials_wrong_logging_mode() {
+        let credentials = LogCredentials::DiscordLog {
+            key: "asd".into(),
+            channel: 12345,
+            critical_channel: None,
         };
 
-        get_directory(&config);
+        parse_file_credentials(&credentials);
     }
-}
\ No newline at end of file
+}
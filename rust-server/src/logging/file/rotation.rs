@@ -0,0 +1,318 @@
+use crate::parse_config::FileRotation;
+use chrono::NaiveDate;
+use flate2::{Compression, write::GzEncoder};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+
+// Appends records to a single log file, moving it aside once it gets too big or a new day starts.
+// Rotated files are numbered from newest to oldest (log.1, log.2, ...) and only the configured
+// number of them is kept.
+pub struct RotatingWriter {
+    path: PathBuf,
+    rotation: FileRotation,
+
+    // The size of the current log file [in bytes], tracked to avoid reading its metadata on every write
+    size: u64,
+
+    // The day the current log file was started on
+    started_on: NaiveDate,
+}
+
+
+impl RotatingWriter {
+    pub fn new(path: PathBuf, rotation: FileRotation) -> RotatingWriter {
+        let metadata = fs::metadata(&path).ok();
+        let size = metadata.as_ref().map(|metadata| metadata.len()).unwrap_or(0);
+
+        // An existing log file continues from the day it was last written to.
+        let started_on = metadata
+            .and_then(|metadata| metadata.modified().ok())
+            .map(|modified| chrono::DateTime::<chrono::Local>::from(modified).naive_local().date())
+            .unwrap_or_else(today);
+
+        RotatingWriter {
+            path,
+            rotation,
+            size,
+            started_on,
+        }
+    }
+
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+
+    pub fn write_record(&mut self, record: &str) -> io::Result<()> {
+        self.write_record_on(record, today())
+    }
+
+
+    fn write_record_on(&mut self, record: &str, day: NaiveDate) -> io::Result<()> {
+        if self.needs_rotation(record.len() as u64, day) {
+            self.rotate()?;
+        }
+
+        if self.size == 0 { self.started_on = day }
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?;
+
+        file.write_all(record.as_bytes())?;
+        self.size += record.len() as u64;
+        Ok(())
+    }
+
+
+    // An empty log file is never rotated, so a single record bigger than the limit still gets written.
+    fn needs_rotation(&self, record_size: u64, day: NaiveDate) -> bool {
+        if self.size == 0 { return false }
+
+        let too_big = match self.rotation.max_size {
+            Some(max_size) => self.size + record_size > max_size,
+            None => false,
+        };
+        let new_day = self.rotation.daily && day != self.started_on;
+
+        too_big || new_day
+    }
+
+
+    // Shift every kept log file one number up, dropping the oldest, and move the current one to number 1.
+    // A current file which was already moved or deleted by someone else leaves nothing to rotate.
+    fn rotate(&mut self) -> io::Result<()> {
+        let keep = self.rotation.keep;
+
+        if keep == 0 {
+            ignore_not_found(fs::remove_file(&self.path))?;
+            self.size = 0;
+            return Ok(());
+        }
+
+        remove_if_exists(&self.rotated_path(keep))?;
+
+        for number in (1..keep).rev() {
+            let from = self.rotated_path(number);
+            if from.exists() {
+                ignore_not_found(fs::rename(&from, self.rotated_path(number + 1)))?;
+            }
+        }
+
+        let newest = with_suffix(&self.path, ".1");
+        match fs::rename(&self.path, &newest) {
+            Ok(()) => {
+                self.size = 0;
+                if self.rotation.compress { compress(&newest)? }
+                Ok(())
+            },
+
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                self.size = 0;
+                Ok(())
+            },
+
+            Err(err) => Err(err),
+        }
+    }
+
+
+    fn rotated_path(&self, number: u32) -> PathBuf {
+        let suffix = if self.rotation.compress { format!(".{}.gz", number) } else { format!(".{}", number) };
+        with_suffix(&self.path, &suffix)
+    }
+}
+
+
+fn today() -> NaiveDate {
+    chrono::Local::now().naive_local().date()
+}
+
+
+fn with_suffix(path: &Path, suffix: &str) -> PathBuf {
+    let mut path = path.as_os_str().to_owned();
+    path.push(suffix);
+    PathBuf::from(path)
+}
+
+
+fn remove_if_exists(path: &Path) -> io::Result<()> {
+    ignore_not_found(fs::remove_file(path))
+}
+
+
+fn ignore_not_found(result: io::Result<()>) -> io::Result<()> {
+    match result {
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(()),
+        result => result,
+    }
+}
+
+
+// Replace the file with a gzipped copy of it, named the same with a .gz suffix.
+fn compress(path: &Path) -> io::Result<()> {
+    let mut source = fs::File::open(path)?;
+    let target = fs::File::create(with_suffix(path, ".gz"))?;
+    let mut encoder = GzEncoder::new(target, Compression::default());
+
+    io::copy(&mut source, &mut encoder)?;
+    encoder.finish()?;
+    fs::remove_file(path)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_config::FileRotation;
+    use chrono::NaiveDate;
+    use flate2::read::GzDecoder;
+    use std::fs;
+    use std::io::Read;
+    use std::path::PathBuf;
+    use super::RotatingWriter;
+
+
+    // Each test uses its own directory, as they run at the same time.
+    fn test_dir(name: &str) -> PathBuf {
+        let dir = std::env::current_dir().unwrap().join(name);
+        fs::create_dir(&dir).unwrap();
+        dir
+    }
+
+
+    fn rotation(max_size: Option<u64>, daily: bool, keep: u32, compress: bool) -> FileRotation {
+        FileRotation { max_size, daily, keep, compress }
+    }
+
+
+    #[test]
+    fn write_record_rotates_by_size() {
+        let dir = test_dir("TEST_ROTATE_SIZE");
+        let mut writer = RotatingWriter::new(dir.join("warn.log"), rotation(Some(10), false, 2, false));
+        let day = NaiveDate::from_ymd(2021, 6, 1);
+
+        for record in ["first\n", "second\n", "third\n", "fourth\n"].iter() {
+            writer.write_record_on(record, day).unwrap();
+        }
+
+        let current = fs::read_to_string(dir.join("warn.log")).unwrap();
+        let newest = fs::read_to_string(dir.join("warn.log.1")).unwrap();
+        let oldest = fs::read_to_string(dir.join("warn.log.2")).unwrap();
+        let dropped = dir.join("warn.log.3").exists();
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(current, "fourth\n");
+        assert_eq!(newest, "third\n");
+        assert_eq!(oldest, "second\n");
+        assert!(!dropped);
+    }
+
+
+    #[test]
+    fn write_record_rotates_daily() {
+        let dir = test_dir("TEST_ROTATE_DAILY");
+        let mut writer = RotatingWriter::new(dir.join("warn.log"), rotation(None, true, 5, false));
+
+        writer.write_record_on("first\n", NaiveDate::from_ymd(2021, 6, 1)).unwrap();
+        writer.write_record_on("second\n", NaiveDate::from_ymd(2021, 6, 1)).unwrap();
+        writer.write_record_on("third\n", NaiveDate::from_ymd(2021, 6, 2)).unwrap();
+
+        let current = fs::read_to_string(dir.join("warn.log")).unwrap();
+        let newest = fs::read_to_string(dir.join("warn.log.1")).unwrap();
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(current, "third\n");
+        assert_eq!(newest, "first\nsecond\n");
+    }
+
+
+    #[test]
+    fn write_record_compresses_rotated() {
+        let dir = test_dir("TEST_ROTATE_COMPRESS");
+        let mut writer = RotatingWriter::new(dir.join("warn.log"), rotation(Some(10), false, 3, true));
+        let day = NaiveDate::from_ymd(2021, 6, 1);
+
+        writer.write_record_on("first\n", day).unwrap();
+        writer.write_record_on("second\n", day).unwrap();
+
+        let mut newest = String::new();
+        let compressed = fs::File::open(dir.join("warn.log.1.gz")).unwrap();
+        GzDecoder::new(compressed).read_to_string(&mut newest).unwrap();
+        let uncompressed_left = dir.join("warn.log.1").exists();
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(newest, "first\n");
+        assert!(!uncompressed_left);
+    }
+
+
+    #[test]
+    fn write_record_keeps_none() {
+        let dir = test_dir("TEST_ROTATE_KEEP_NONE");
+        let mut writer = RotatingWriter::new(dir.join("warn.log"), rotation(Some(10), false, 0, false));
+        let day = NaiveDate::from_ymd(2021, 6, 1);
+
+        writer.write_record_on("first\n", day).unwrap();
+        writer.write_record_on("second\n", day).unwrap();
+
+        let files = fs::read_dir(&dir).unwrap().count();
+        let current = fs::read_to_string(dir.join("warn.log")).unwrap();
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(files, 1);
+        assert_eq!(current, "second\n");
+    }
+
+
+    #[test]
+    fn new_continues_existing_file() {
+        let dir = test_dir("TEST_ROTATE_EXISTING");
+        fs::write(dir.join("warn.log"), "existing\n").unwrap();
+
+        let mut writer = RotatingWriter::new(dir.join("warn.log"), rotation(Some(16), false, 1, false));
+        writer.write_record_on("second\n", NaiveDate::from_ymd(2021, 6, 1)).unwrap();
+        writer.write_record_on("third\n", NaiveDate::from_ymd(2021, 6, 1)).unwrap();
+
+        let current = fs::read_to_string(dir.join("warn.log")).unwrap();
+        let newest = fs::read_to_string(dir.join("warn.log.1")).unwrap();
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(current, "third\n");
+        assert_eq!(newest, "existing\nsecond\n");
+    }
+
+    #[test]
+    fn write_record_continues_after_removed_file() {
+        let dir = test_dir("TEST_ROTATE_REMOVED");
+        let mut writer = RotatingWriter::new(dir.join("warn.log"), rotation(Some(10), false, 2, false));
+        let day = NaiveDate::from_ymd(2021, 6, 1);
+
+        writer.write_record_on("first\n", day).unwrap();
+        fs::remove_file(dir.join("warn.log")).unwrap();
+        let written = writer.write_record_on("second\n", day);
+
+        let current = fs::read_to_string(dir.join("warn.log")).unwrap();
+        let rotated = dir.join("warn.log.1").exists();
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert!(written.is_ok());
+        assert_eq!(current, "second\n");
+        assert!(!rotated);
+    }
+}
@@ -0,0 +1,779 @@
+use crate::metrics::{ComponentTemperature, alert::{Alert, AlertState}, disk_io::DiskIoUsage, interval::IntervalMetrics, network::NetworkUsage, process::{ProcessUsage, ProcessStatus}, warn::{Severity, Warn}};
+use super::Sample;
+use chrono::SecondsFormat;
+use serde_json::{json, Map, Value};
+use sysinfo::{System, SystemExt};
+use std::time::Duration;
+
+
+// How many characters of a process' command line are shown in the text fields.
+const MAX_CMD_LENGTH: usize = 60;
+
+
+// The time the sample was taken, in RFC 3339 (ex: 2021-06-01T10:00:00Z).
+pub fn timestamp(sample: &Sample) -> String {
+    sample.taken_at.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+
+// A single line telling which server the sample is from and when it was taken,
+// ex: "web-1 (host.example) - 2021-06-01T10:00:00Z".
+pub fn identity(sample: &Sample) -> String {
+    match sample.instance_name {
+        Some(instance_name) => format!("{} ({}) - {}", instance_name, sample.hostname, timestamp(sample)),
+        None => format!("{} - {}", sample.hostname, timestamp(sample)),
+    }
+}
+
+
+// Create a JSON record for the passed mode, with the sample's identity and the metrics under the passed key.
+pub fn record_json(sample: &Sample, mode: &str, key: &str, metrics: Value) -> Value {
+    let mut record = json!({
+        "timestamp": timestamp(sample),
+        "hostname": sample.hostname,
+        "instance_name": sample.instance_name,
+        "mode": mode,
+    });
+    record[key] = metrics;
+
+    record
+}
+
+
+// Check each interval metric. If it is enabled, return its name together with its formatted value.
+// Used by every sink, so the metrics read the same no matter where they are logged.
+pub fn interval_fields(metrics: &IntervalMetrics, system: &System) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+
+    if let Some(ram) = metrics.ram {
+        fields.push(("Used RAM", format!("{} MB out of {} MB", ram / 1000, system.get_total_memory() / 1000)));
+    }
+
+    if let Some(swap) = metrics.swap {
+        fields.push(("Used Swap", format!("{} MB out of {} MB", swap / 1000, system.get_total_swap() / 1000)));
+    }
+
+    if let Some(cpu) = metrics.cpu {
+        fields.push(("Used CPU", format!("{:.2}%", cpu)));
+    }
+
+    if let Some(cpu_cores) = &metrics.cpu_cores {
+        // Rounded and space separated, so even a lot of cores fit on a single line.
+        let cores = cpu_cores
+            .iter()
+            .map(|core| format!("{:.0}%", core))
+            .collect::<Vec<String>>();
+
+        fields.push(("Used CPU per Core", cores.join(" ")));
+    }
+
+    if let Some((min, five_min, fteen_min)) = metrics.cpu_average {
+        fields.push((
+            "CPU Average",
+            format!("1 minute - {:.2}%, 5 minutes - {:.2}%, 15 minutes - {:.2}%", min, five_min, fteen_min)
+        ));
+    }
+
+    if let Some(system_uptime) = metrics.system_uptime {
+        fields.push(("System Uptime", format!("{} minutes", system_uptime / 60)));
+    }
+
+    if let Some(disk) = &metrics.disk {
+        // Every watched mount point shares a single field, to keep the message short.
+        let mounts = disk
+            .iter()
+            .map(|usage| format!("{} {} MB out of {} MB", usage.mount_point, usage.used, usage.total))
+            .collect::<Vec<String>>();
+
+        if !mounts.is_empty() { fields.push(("Used Disk Space", mounts.join(", "))) }
+    }
+
+    if let Some(processes) = &metrics.processes {
+        if !processes.is_empty() { fields.push(("Watched Processes", status_lines(processes))) }
+    }
+
+    if let Some(network) = &metrics.network {
+        if !network.is_empty() { fields.push(("Network Traffic", network_lines(network))) }
+    }
+
+    if let Some(disk_io) = &metrics.disk_io {
+        if !disk_io.is_empty() { fields.push(("Disk I/O", disk_io_lines(disk_io))) }
+    }
+
+    if let Some(temperature) = &metrics.temperature {
+        if !temperature.is_empty() { fields.push(("Temperature", temperature_lines(temperature))) }
+    }
+
+    fields
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// The separate messages the chat sinks send the alerts in, each with its own title and colour
+pub enum AlertGroup {
+    // The alerts which started or were reminded of
+    Firing(Severity),
+    Resolved(Severity),
+}
+
+
+impl AlertGroup {
+    pub fn of(alert: &Alert) -> AlertGroup {
+        match alert.state {
+            AlertState::Resolved => AlertGroup::Resolved(alert.warn.severity()),
+            AlertState::Firing | AlertState::Reminder => AlertGroup::Firing(alert.warn.severity()),
+        }
+    }
+
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            AlertGroup::Firing(severity) | AlertGroup::Resolved(severity) => *severity,
+        }
+    }
+
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            AlertGroup::Firing(Severity::Critical) => "Server Critical Firing",
+            AlertGroup::Firing(Severity::Warning) => "Server Warn Firing",
+            AlertGroup::Resolved(Severity::Critical) => "Server Critical Resolved",
+            AlertGroup::Resolved(Severity::Warning) => "Server Warn Resolved",
+        }
+    }
+}
+
+
+// Group the alerts into the messages they are sent in, skipping the empty ones. The firing critical
+// alerts come first, then the firing warnings and at last the resolved ones.
+pub fn group_alerts(alerts: &[Alert]) -> Vec<(AlertGroup, Vec<&Alert>)> {
+    let groups = [
+        AlertGroup::Firing(Severity::Critical),
+        AlertGroup::Firing(Severity::Warning),
+        AlertGroup::Resolved(Severity::Critical),
+        AlertGroup::Resolved(Severity::Warning),
+    ];
+
+    groups
+    .iter()
+    .map(|group| (*group, alerts.iter().filter(|alert| AlertGroup::of(alert) == *group).collect::<Vec<&Alert>>()))
+    .filter(|(_, alerts)| !alerts.is_empty())
+    .collect()
+}
+
+
+// Return the fields of the alert's warning. A reminder tells how long the warning has been firing for,
+// a resolved alert only tells when it resolved, under the warning's name.
+pub fn alert_fields(alert: &Alert, system: &System) -> Vec<(&'static str, String)> {
+    let mut fields = warn_fields(&alert.warn, system);
+
+    match alert.state {
+        AlertState::Firing => {},
+        AlertState::Reminder => fields[0].1 = format!("{}, firing for {}", fields[0].1, duration_text(alert.duration)),
+        AlertState::Resolved => fields = vec![(fields[0].0, format!("Back to normal after {}", duration_text(alert.duration)))],
+    }
+
+    fields
+}
+
+
+// Return the warning's name together with its formatted value. The top processes of a
+// warning follow it as a separate field, one process per line.
+fn warn_fields(warn: &Warn, system: &System) -> Vec<(&'static str, String)> {
+    let mut fields = Vec::new();
+
+    match warn {
+        Warn::HighRAM(_, ram, processes) => {
+            fields.push(("High RAM Usage", format!("{:.2}% out of {} MB", ram, system.get_total_memory() / 1000)));
+            if !processes.is_empty() { fields.push(("Top RAM Processes", process_lines(processes))) }
+        },
+        Warn::HighCPU(_, cpu, processes) => {
+            fields.push(("High CPU Usage", format!("{:.2}%", cpu)));
+            if !processes.is_empty() { fields.push(("Top CPU Processes", process_lines(processes))) }
+        },
+        Warn::HighCPUCores(_, cores) => {
+            let cores = cores
+                .iter()
+                .map(|(core, used)| format!("core {} {:.2}%", core, used))
+                .collect::<Vec<String>>();
+
+            fields.push(("High CPU Core Usage", cores.join(", ")));
+        },
+        Warn::HighDisk(_, mount_point, disk) => {
+            fields.push(("High Disk Space Usage", format!("{:.2}% on {}", disk, mount_point)));
+        },
+        Warn::HighSwap(_, swap) => {
+            fields.push(("High Swap Usage", format!("{:.2}% out of {} MB", swap, system.get_total_swap() / 1000)));
+        },
+        Warn::ProcessDown(_, name) => {
+            fields.push(("Process Down", name.clone()));
+        },
+        Warn::HighNetworkRX(_, interface, mbits) => {
+            fields.push(("High Network Receive", format!("{:.2} Mbit/s on {}", mbits, interface)));
+        },
+        Warn::HighNetworkTX(_, interface, mbits) => {
+            fields.push(("High Network Transmit", format!("{:.2} Mbit/s on {}", mbits, interface)));
+        },
+        Warn::HighDiskIO(_, device, utilisation) => {
+            fields.push(("High Disk I/O Utilisation", format!("{:.2}% on {}", utilisation, device)));
+        },
+        Warn::HighTemperature(_, label, current) => {
+            fields.push(("High Temperature", format!("{:.1}°C on {}", current, label)));
+        },
+        Warn::NearCriticalTemperature(_, label, current, critical) => {
+            fields.push(("Near Critical Temperature", format!("{:.1}°C out of {:.1}°C on {}", current, critical, label)));
+        },
+        Warn::LowFreeRAM(_, available) => {
+            fields.push(("Low Free RAM", format!("{} MB available", available / 1_000_000)));
+        },
+        Warn::LowFreeDisk(_, mount_point, available) => {
+            fields.push(("Low Free Disk Space", format!("{} MB available on {}", available / 1_000_000, mount_point)));
+        },
+        Warn::HighLoad(_, minutes, load) => {
+            fields.push(("High Load Average", format!("{:.2} over {} minutes", load, minutes)));
+        },
+    }
+
+    fields
+}
+
+
+// Short enough to read at a glance, ex: "45 seconds" or "90 minutes".
+fn duration_text(duration: Duration) -> String {
+    match duration.as_secs() {
+        seconds if seconds < 60 => format!("{} seconds", seconds),
+        seconds => format!("{} minutes", seconds / 60),
+    }
+}
+
+
+// Put each process on its own line, ex: "1234 postgres - 45.20% CPU, 512 MB - postgres -D /var/lib/pgsql".
+// Long command lines are cut, to keep the messages within the size limits of discord and slack.
+fn process_lines(processes: &[ProcessUsage]) -> String {
+    processes
+    .iter()
+    .map(|process| {
+        let line = format!("{} {} - {:.2}% CPU, {} MB", process.pid, process.name, process.cpu, process.memory / 1000);
+
+        if process.cmd.is_empty() {
+            line
+        } else if process.cmd.chars().count() > MAX_CMD_LENGTH {
+            format!("{} - {}...", line, process.cmd.chars().take(MAX_CMD_LENGTH).collect::<String>())
+        } else {
+            format!("{} - {}", line, process.cmd)
+        }
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+
+// Put each watched process on its own line, ex: "nginx: 5 running, 1.50% CPU, 120 MB, 9 threads, up 60 minutes".
+fn status_lines(processes: &[ProcessStatus]) -> String {
+    processes
+    .iter()
+    .map(|status| {
+        if status.running == 0 {
+            format!("{}: down", status.name)
+        } else {
+            format!(
+                "{}: {} running, {:.2}% CPU, {} MB, {} threads, up {} minutes",
+                status.name, status.running, status.cpu, status.memory / 1000, status.threads, status.uptime / 60
+            )
+        }
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+
+// Put each network interface on its own line,
+// ex: "eth0: 100.00 Mbit/s in, 8.00 Mbit/s out, 12500 / 1000 packets/s, 2 / 0 errors".
+fn network_lines(network: &[NetworkUsage]) -> String {
+    network
+    .iter()
+    .map(|usage| format!(
+        "{}: {:.2} Mbit/s in, {:.2} Mbit/s out, {:.0} / {:.0} packets/s, {} / {} errors",
+        usage.interface, usage.rx_mbits(), usage.tx_mbits(), usage.rx_packets, usage.tx_packets, usage.rx_errors, usage.tx_errors
+    ))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+
+// Put each block device on its own line,
+// ex: "sda: 12.50 MB/s read, 3.00 MB/s written, 150 / 40 IOPS, 2.50 ms latency, 35.00% busy".
+fn disk_io_lines(disk_io: &[DiskIoUsage]) -> String {
+    disk_io
+    .iter()
+    .map(|usage| format!(
+        "{}: {:.2} MB/s read, {:.2} MB/s written, {:.0} / {:.0} IOPS, {:.2} ms latency, {:.2}% busy",
+        usage.device, usage.read_bytes / 1_000_000.0, usage.write_bytes / 1_000_000.0,
+        usage.read_iops, usage.write_iops, usage.latency, usage.utilisation
+    ))
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+
+// Put each sensor on its own line, ex: "coretemp Package id 0: 55.0°C (max 70.0°C, critical 100.0°C)".
+fn temperature_lines(temperature: &[ComponentTemperature]) -> String {
+    temperature
+    .iter()
+    .map(|sensor| match sensor.critical {
+        Some(critical) => format!("{}: {:.1}°C (max {:.1}°C, critical {:.1}°C)", sensor.label, sensor.current, sensor.max, critical),
+        None => format!("{}: {:.1}°C (max {:.1}°C)", sensor.label, sensor.current, sensor.max),
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+
+// Put each process in a JSON list, keeping its full command line.
+fn processes_json(processes: &[ProcessUsage]) -> Value {
+    Value::Array(
+        processes.iter().map(|process| json!({
+            "pid": process.pid,
+            "name": process.name,
+            "cmd": process.cmd,
+            "cpu_percent": process.cpu,
+            "memory_kb": process.memory,
+        }))
+        .collect()
+    )
+}
+
+
+// Put each enabled interval metric in a JSON object, keeping the raw units reported by the system.
+pub fn interval_json(metrics: &IntervalMetrics, system: &System) -> Value {
+    let mut fields = Map::new();
+
+    if let Some(ram) = metrics.ram {
+        fields.insert("ram".into(), json!({ "used_kb": ram, "total_kb": system.get_total_memory() }));
+    }
+
+    if let Some(swap) = metrics.swap {
+        fields.insert("swap".into(), json!({ "used_kb": swap, "total_kb": system.get_total_swap() }));
+    }
+
+    if let Some(cpu) = metrics.cpu {
+        fields.insert("cpu".into(), json!({ "used_percent": cpu }));
+    }
+
+    if let Some(cpu_cores) = &metrics.cpu_cores {
+        fields.insert("cpu_cores".into(), json!({ "used_percent": cpu_cores }));
+    }
+
+    if let Some((one, five, fifteen)) = metrics.cpu_average {
+        fields.insert("cpu_average".into(), json!({ "one": one, "five": five, "fifteen": fifteen }));
+    }
+
+    if let Some(system_uptime) = metrics.system_uptime {
+        fields.insert("system_uptime".into(), json!({ "seconds": system_uptime }));
+    }
+
+    if let Some(disk) = &metrics.disk {
+        let mounts = disk
+            .iter()
+            .map(|usage| json!({ "mount_point": usage.mount_point, "used_mb": usage.used, "total_mb": usage.total }))
+            .collect();
+
+        fields.insert("disk".into(), Value::Array(mounts));
+    }
+
+    if let Some(processes) = &metrics.processes {
+        let processes = processes
+            .iter()
+            .map(|status| json!({
+                "name": status.name,
+                "running": status.running,
+                "cpu_percent": status.cpu,
+                "memory_kb": status.memory,
+                "threads": status.threads,
+                "uptime_seconds": status.uptime,
+            }))
+            .collect();
+
+        fields.insert("processes".into(), Value::Array(processes));
+    }
+
+    if let Some(network) = &metrics.network {
+        let interfaces = network
+            .iter()
+            .map(|usage| json!({
+                "interface": usage.interface,
+                "rx_bytes_per_second": usage.rx_bytes,
+                "tx_bytes_per_second": usage.tx_bytes,
+                "rx_packets_per_second": usage.rx_packets,
+                "tx_packets_per_second": usage.tx_packets,
+                "rx_errors": usage.rx_errors,
+                "tx_errors": usage.tx_errors,
+            }))
+            .collect();
+
+        fields.insert("network".into(), Value::Array(interfaces));
+    }
+
+    if let Some(disk_io) = &metrics.disk_io {
+        let devices = disk_io
+            .iter()
+            .map(|usage| json!({
+                "device": usage.device,
+                "read_bytes_per_second": usage.read_bytes,
+                "write_bytes_per_second": usage.write_bytes,
+                "read_iops": usage.read_iops,
+                "write_iops": usage.write_iops,
+                "latency_ms": usage.latency,
+                "utilisation_percent": usage.utilisation,
+            }))
+            .collect();
+
+        fields.insert("disk_io".into(), Value::Array(devices));
+    }
+
+    if let Some(temperature) = &metrics.temperature {
+        let sensors = temperature
+            .iter()
+            .map(|sensor| json!({
+                "label": sensor.label,
+                "current_celsius": sensor.current,
+                "max_celsius": sensor.max,
+                "critical_celsius": sensor.critical,
+            }))
+            .collect();
+
+        fields.insert("temperature".into(), Value::Array(sensors));
+    }
+
+    Value::Object(fields)
+}
+
+
+// Put each alert in a JSON list, with its severity, its state and how long its warning has been firing for.
+pub fn alert_json(alerts: &[Alert]) -> Value {
+    Value::Array(
+        alerts.iter().map(|alert| {
+            let mut alert_json = warn_json(&alert.warn);
+            alert_json["severity"] = json!(alert.warn.severity().name());
+            alert_json["state"] = json!(alert.state.name());
+            alert_json["duration_seconds"] = json!(alert.duration.as_secs());
+
+            alert_json
+        })
+        .collect()
+    )
+}
+
+
+// Name the metric which surpassed its limit, together with the values it was surpassed with.
+fn warn_json(warn: &Warn) -> Value {
+    match warn {
+        Warn::HighCPUCores(_, cores) => {
+            let cores = cores
+                .iter()
+                .map(|(core, used)| json!({ "core": core, "used_percent": used }))
+                .collect::<Vec<Value>>();
+
+            json!({ "metric": warn.metric_name(), "cores": cores })
+        },
+        Warn::HighDisk(_, mount_point, used) => {
+            json!({ "metric": warn.metric_name(), "mount_point": mount_point, "used_percent": used })
+        },
+        Warn::HighRAM(_, used, processes) | Warn::HighCPU(_, used, processes) => {
+            json!({ "metric": warn.metric_name(), "used_percent": used, "top_processes": processes_json(processes) })
+        },
+        Warn::HighSwap(_, used) => {
+            json!({ "metric": warn.metric_name(), "used_percent": used })
+        },
+        Warn::ProcessDown(_, name) => {
+            json!({ "metric": warn.metric_name(), "name": name })
+        },
+        Warn::HighNetworkRX(_, interface, mbits) | Warn::HighNetworkTX(_, interface, mbits) => {
+            json!({ "metric": warn.metric_name(), "interface": interface, "mbits_per_second": mbits })
+        },
+        Warn::HighDiskIO(_, device, utilisation) => {
+            json!({ "metric": warn.metric_name(), "device": device, "utilisation_percent": utilisation })
+        },
+        Warn::HighTemperature(_, label, current) => {
+            json!({ "metric": warn.metric_name(), "label": label, "current_celsius": current })
+        },
+        Warn::NearCriticalTemperature(_, label, current, critical) => {
+            json!({ "metric": warn.metric_name(), "label": label, "current_celsius": current, "critical_celsius": critical })
+        },
+        Warn::LowFreeRAM(_, available) => {
+            json!({ "metric": warn.metric_name(), "available_bytes": available })
+        },
+        Warn::LowFreeDisk(_, mount_point, available) => {
+            json!({ "metric": warn.metric_name(), "mount_point": mount_point, "available_bytes": available })
+        },
+        Warn::HighLoad(_, _, load) => {
+            json!({ "metric": warn.metric_name(), "load": load })
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use sysinfo::{System, SystemExt};
+    use crate::logging::Sample;
+    use crate::metrics::{ComponentTemperature, MountUsage, alert::{Alert, AlertState}, interval::IntervalMetrics, network::NetworkUsage, process::{ProcessUsage, ProcessStatus}, warn::{Limit, Severity, WarnMetrics, Warn}};
+    use chrono::{TimeZone, Utc};
+    use serde_json::{json, Value};
+    use super::{AlertGroup, group_alerts, identity, record_json, interval_fields, alert_fields, warn_fields, interval_json, alert_json, warn_json};
+    use std::time::Duration;
+
+
+    fn sample<'a>(system: &'a System, instance_name: Option<&'a str>) -> Sample<'a> {
+        Sample {
+            system,
+            taken_at: Utc.ymd(2021, 6, 1).and_hms(10, 0, 0),
+            hostname: "host.example",
+            instance_name,
+        }
+    }
+
+
+    #[test]
+    fn identity_names_host() {
+        let system = System::new();
+
+        assert_eq!(identity(&sample(&system, None)), "host.example - 2021-06-01T10:00:00Z");
+        assert_eq!(identity(&sample(&system, Some("web-1"))), "web-1 (host.example) - 2021-06-01T10:00:00Z");
+    }
+
+
+    #[test]
+    fn record_json_adds_identity() {
+        let system = System::new();
+        let record = record_json(&sample(&system, Some("web-1")), "warn", "warnings", json!([]));
+
+        assert_eq!(record, json!({
+            "timestamp": "2021-06-01T10:00:00Z",
+            "hostname": "host.example",
+            "instance_name": "web-1",
+            "mode": "warn",
+            "warnings": [],
+        }));
+    }
+
+
+    #[test]
+    fn interval_fields_skips_disabled() {
+        let system = System::new();
+        let metrics = IntervalMetrics {
+            cpu: Some(12.5),
+            cpu_cores: Some(vec![10.4, 99.6]),
+            system_uptime: Some(600),
+            disk: Some(vec![
+                MountUsage { mount_point: "/".into(), used: 2048, total: 4096 },
+                MountUsage { mount_point: "/var".into(), used: 100, total: 1000 },
+            ]),
+            processes: Some(vec![
+                ProcessStatus { name: "web".into(), running: 2, cpu: 1.5, memory: 120_000, threads: 9, uptime: 3600 },
+                ProcessStatus { name: "db".into(), running: 0, cpu: 0.0, memory: 0, threads: 0, uptime: 0 },
+            ]),
+            network: Some(vec![NetworkUsage {
+                interface: "eth0".into(),
+                rx_bytes: 12_500_000.0,
+                tx_bytes: 1_000_000.0,
+                rx_packets: 12_500.0,
+                tx_packets: 1000.0,
+                rx_errors: 2,
+                tx_errors: 0,
+            }]),
+            temperature: Some(vec![
+                ComponentTemperature { label: "cpu".into(), current: 55.0, max: 70.0, critical: Some(100.0) },
+                ComponentTemperature { label: "acpitz".into(), current: 40.0, max: 40.0, critical: None },
+            ]),
+            ..IntervalMetrics::default()
+        };
+
+        let fields = interval_fields(&metrics, &system);
+
+        assert_eq!(fields, vec![
+            ("Used CPU", "12.50%".to_string()),
+            ("Used CPU per Core", "10% 100%".to_string()),
+            ("System Uptime", "10 minutes".to_string()),
+            ("Used Disk Space", "/ 2048 MB out of 4096 MB, /var 100 MB out of 1000 MB".to_string()),
+            ("Watched Processes", "web: 2 running, 1.50% CPU, 120 MB, 9 threads, up 60 minutes\ndb: down".to_string()),
+            ("Network Traffic", "eth0: 100.00 Mbit/s in, 8.00 Mbit/s out, 12500 / 1000 packets/s, 2 / 0 errors".to_string()),
+            ("Temperature", "cpu: 55.0°C (max 70.0°C, critical 100.0°C)\nacpitz: 40.0°C (max 40.0°C)".to_string()),
+        ]);
+    }
+
+
+    #[test]
+    fn warn_fields_formats_each_warn() {
+        let system = System::new();
+        let metrics = WarnMetrics {
+            cpu: Limit::new(10.0, 0.0),
+            disk: Limit::new(10.0, 0.0),
+            warnings: vec![
+                Warn::HighCPU(Severity::Warning, 70.0, vec![
+                    ProcessUsage { pid: 42, name: "yes".into(), cmd: "yes".into(), cpu: 99.5, memory: 2000 },
+                    ProcessUsage { pid: 7, name: "kworker".into(), cmd: String::new(), cpu: 1.0, memory: 0 },
+                    ProcessUsage { pid: 9, name: "java".into(), cmd: format!("java {}", "-X".repeat(40)), cpu: 0.5, memory: 0 },
+                ]),
+                Warn::HighCPUCores(Severity::Warning, vec![(1, 95.0), (3, 99.5)]),
+                Warn::HighDisk(Severity::Warning, "/var".into(), 95.5),
+                Warn::ProcessDown(Severity::Warning, "web".into()),
+                Warn::HighNetworkTX(Severity::Warning, "eth0".into(), 950.5),
+                Warn::HighTemperature(Severity::Warning, "cpu".into(), 85.25),
+            ],
+            ..WarnMetrics::default()
+        };
+
+        let fields = metrics.warnings.iter().flat_map(|warn| warn_fields(warn, &system)).collect::<Vec<(&str, String)>>();
+
+        assert_eq!(fields, vec![
+            ("High CPU Usage", "70.00%".to_string()),
+            ("Top CPU Processes", format!(
+                "42 yes - 99.50% CPU, 2 MB - yes\n7 kworker - 1.00% CPU, 0 MB\n9 java - 0.50% CPU, 0 MB - java {}-...",
+                "-X".repeat(27)
+            )),
+            ("High CPU Core Usage", "core 1 95.00%, core 3 99.50%".to_string()),
+            ("High Disk Space Usage", "95.50% on /var".to_string()),
+            ("Process Down", "web".to_string()),
+            ("High Network Transmit", "950.50 Mbit/s on eth0".to_string()),
+            ("High Temperature", "85.2°C on cpu".to_string()),
+        ]);
+    }
+
+
+    #[test]
+    fn alert_fields_formats_each_state() {
+        let system = System::new();
+        let alert = |state: AlertState, seconds: u64| Alert {
+            state,
+            warn: Warn::HighCPU(Severity::Warning, 70.0, vec![
+                ProcessUsage { pid: 42, name: "yes".into(), cmd: String::new(), cpu: 99.5, memory: 0 },
+            ]),
+            duration: Duration::from_secs(seconds),
+        };
+
+        assert_eq!(alert_fields(&alert(AlertState::Firing, 0), &system), vec![
+            ("High CPU Usage", "70.00%".to_string()),
+            ("Top CPU Processes", "42 yes - 99.50% CPU, 0 MB".to_string()),
+        ]);
+        assert_eq!(alert_fields(&alert(AlertState::Reminder, 1800), &system)[0], ("High CPU Usage", "70.00%, firing for 30 minutes".to_string()));
+        assert_eq!(alert_fields(&alert(AlertState::Resolved, 45), &system), vec![
+            ("High CPU Usage", "Back to normal after 45 seconds".to_string()),
+        ]);
+    }
+
+
+    #[test]
+    fn interval_json_skips_disabled() {
+        let system = System::new();
+        let metrics = IntervalMetrics {
+            cpu: Some(12.5),
+            system_uptime: Some(600),
+            disk: Some(vec![MountUsage { mount_point: "/".into(), used: 2048, total: 4096 }]),
+            processes: Some(vec![
+                ProcessStatus { name: "web".into(), running: 2, cpu: 1.5, memory: 120_000, threads: 9, uptime: 3600 },
+            ]),
+            network: Some(vec![NetworkUsage {
+                interface: "eth0".into(),
+                rx_bytes: 1000.0,
+                tx_bytes: 500.0,
+                rx_packets: 10.0,
+                tx_packets: 5.0,
+                rx_errors: 0,
+                tx_errors: 1,
+            }]),
+            ..IntervalMetrics::default()
+        };
+
+        let json = interval_json(&metrics, &system);
+
+        assert_eq!(json, json!({
+            "cpu": { "used_percent": 12.5 },
+            "system_uptime": { "seconds": 600 },
+            "disk": [{ "mount_point": "/", "used_mb": 2048, "total_mb": 4096 }],
+            "processes": [{
+                "name": "web", "running": 2, "cpu_percent": 1.5, "memory_kb": 120_000, "threads": 9, "uptime_seconds": 3600,
+            }],
+            "network": [{
+                "interface": "eth0",
+                "rx_bytes_per_second": 1000.0,
+                "tx_bytes_per_second": 500.0,
+                "rx_packets_per_second": 10.0,
+                "tx_packets_per_second": 5.0,
+                "rx_errors": 0,
+                "tx_errors": 1,
+            }],
+        }));
+    }
+
+
+    #[test]
+    fn warn_json_lists_each_warn() {
+        let metrics = WarnMetrics {
+            ram: Limit::new(10.0, 0.0),
+            cpu: Limit::new(10.0, 0.0),
+            warnings: vec![
+                Warn::HighRAM(Severity::Warning, 50.0, vec![
+                    ProcessUsage { pid: 42, name: "java".into(), cmd: "java -jar app.jar".into(), cpu: 3.5, memory: 2048 },
+                ]),
+                Warn::HighCPU(Severity::Warning, 70.5, vec![]),
+                Warn::HighCPUCores(Severity::Warning, vec![(2, 95.0)]),
+                Warn::HighDisk(Severity::Warning, "/".into(), 90.0),
+                Warn::ProcessDown(Severity::Warning, "db".into()),
+                Warn::HighNetworkRX(Severity::Warning, "eth0".into(), 120.0),
+                Warn::HighDiskIO(Severity::Warning, "sda".into(), 97.5),
+                Warn::NearCriticalTemperature(Severity::Warning, "nvme".into(), 81.0, 84.5),
+            ],
+            ..WarnMetrics::default()
+        };
+
+        let json = Value::Array(metrics.warnings.iter().map(warn_json).collect());
+
+        assert_eq!(json, json!([
+            { "metric": "ram", "used_percent": 50.0, "top_processes": [
+                { "pid": 42, "name": "java", "cmd": "java -jar app.jar", "cpu_percent": 3.5, "memory_kb": 2048 },
+            ]},
+            { "metric": "cpu", "used_percent": 70.5, "top_processes": [] },
+            { "metric": "cpu_core", "cores": [{ "core": 2, "used_percent": 95.0 }] },
+            { "metric": "disk", "mount_point": "/", "used_percent": 90.0 },
+            { "metric": "process", "name": "db" },
+            { "metric": "network_rx", "interface": "eth0", "mbits_per_second": 120.0 },
+            { "metric": "disk_io", "device": "sda", "utilisation_percent": 97.5 },
+            { "metric": "temperature_critical", "label": "nvme", "current_celsius": 81.0, "critical_celsius": 84.5 },
+        ]));
+    }
+
+
+    #[test]
+    fn alert_json_adds_state() {
+        let json = alert_json(&[
+            Alert { state: AlertState::Firing, warn: Warn::HighSwap(Severity::Warning, 90.0), duration: Duration::from_secs(0) },
+            Alert { state: AlertState::Resolved, warn: Warn::ProcessDown(Severity::Critical, "db".into()), duration: Duration::from_secs(300) },
+        ]);
+
+        assert_eq!(json, json!([
+            { "metric": "swap", "used_percent": 90.0, "severity": "warning", "state": "firing", "duration_seconds": 0 },
+            { "metric": "process", "name": "db", "severity": "critical", "state": "resolved", "duration_seconds": 300 },
+        ]));
+    }
+
+
+    #[test]
+    fn group_alerts_orders_by_severity() {
+        let alerts = [
+            Alert { state: AlertState::Resolved, warn: Warn::HighSwap(Severity::Warning, 90.0), duration: Duration::from_secs(60) },
+            Alert { state: AlertState::Firing, warn: Warn::HighRAM(Severity::Warning, 85.0, vec![]), duration: Duration::from_secs(0) },
+            Alert { state: AlertState::Reminder, warn: Warn::ProcessDown(Severity::Critical, "db".into()), duration: Duration::from_secs(600) },
+        ];
+
+        let groups = group_alerts(&alerts);
+
+        assert_eq!(groups, vec![
+            (AlertGroup::Firing(Severity::Critical), vec![&alerts[2]]),
+            (AlertGroup::Firing(Severity::Warning), vec![&alerts[1]]),
+            (AlertGroup::Resolved(Severity::Warning), vec![&alerts[0]]),
+        ]);
+        assert_eq!(groups[0].0.title(), "Server Critical Firing");
+        assert_eq!(groups[2].0.title(), "Server Warn Resolved");
+    }
+}
@@ -1,2 +1,79 @@
+use crate::metrics::{interval::IntervalMetrics, warn::WarnMetrics};
+use crate::parse_config::{Config, LogType, LogCredentials};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sysinfo::System;
+
+
 pub mod discord;
-pub mod file;
\ No newline at end of file
+pub mod file;
+pub mod format;
+pub mod webhook;
+pub mod slack;
+pub mod prometheus;
+
+#[cfg(test)]
+mod test_server;
+
+
+// Where and when a snapshot was taken, passed to the sinks next to its metrics.
+pub struct Sample<'a> {
+    // The system the metrics were read from, already refreshed
+    pub system: &'a System,
+    pub taken_at: DateTime<Utc>,
+    pub hostname: &'a str,
+
+    // A user defined label, to tell apart servers which share a hostname
+    pub instance_name: Option<&'a str>,
+}
+
+
+impl<'a> Sample<'a> {
+    // A sample taken right now.
+    pub fn new(system: &'a System, hostname: &'a str, instance_name: Option<&'a str>) -> Sample<'a> {
+        Sample {
+            system,
+            taken_at: Utc::now(),
+            hostname,
+            instance_name,
+        }
+    }
+}
+
+
+// A destination for the watched metrics. The scheduler calls every registered sink with the same
+// snapshot, so a sink only decides how the metrics are sent or stored. The sample carries the
+// refreshed system, for sinks which need more context (ex: total RAM next to the used RAM).
+#[async_trait]
+pub trait Sink: Send {
+    // Called every time the interval mode runs, with all of its enabled metrics.
+    async fn emit_interval(&mut self, metrics: &IntervalMetrics, sample: &Sample<'_>);
+
+    // Called every time the warn mode runs and at least one warning started firing, is due a reminder or resolved.
+    async fn emit_warn(&mut self, metrics: &WarnMetrics, sample: &Sample<'_>);
+
+    // Called every time the warn mode runs, even when no limit was surpassed. Meant for sinks which keep
+    // the current state of the warnings rather than logging them. Does nothing by default.
+    async fn update_warns(&mut self, _metrics: &WarnMetrics, _sample: &Sample<'_>) {}
+}
+
+
+// Create a sink for each of the (type, credentials) pairs of the config, failing if any of them can't be created.
+pub fn create_sinks(config: &Config) -> Result<Vec<Box<dyn Sink>>, String> {
+    config.logs
+        .iter()
+        .map(|(log_type, log_credentials)| create_sink(log_type, log_credentials))
+        .collect()
+}
+
+
+// Create the sink of a logging method. Fails if it can't be started (ex: the prometheus_address is already in use).
+pub fn create_sink(log_type: &LogType, log_credentials: &LogCredentials) -> Result<Box<dyn Sink>, String> {
+    Ok(match log_type {
+        LogType::Discord => Box::new(discord::DiscordSink::new(log_credentials)),
+        LogType::File => Box::new(file::FileSink::new(log_credentials)),
+        LogType::Webhook => Box::new(webhook::WebhookSink::new(log_credentials)),
+        LogType::Slack => Box::new(slack::SlackSink::new(log_credentials)),
+        LogType::Prometheus => Box::new(prometheus::PrometheusSink::new(log_credentials)?),
+    })
+}
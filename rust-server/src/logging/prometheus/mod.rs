@@ -0,0 +1,581 @@
+use crate::metrics::{ComponentTemperature, disk_io::DiskIoUsage, interval::IntervalMetrics, network::NetworkUsage, process::ProcessStatus, warn::{Limit, Severity, WarnMetrics, Warn}};
+use crate::parse_config::LogCredentials;
+use super::{Sink, Sample};
+use async_trait::async_trait;
+use std::fmt::Write;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+
+mod parsers;
+
+
+// How long to wait before accepting connections again after a failed accept.
+const ACCEPT_RETRY_DELAY: Duration = Duration::from_millis(500);
+
+
+// The latest metrics, already rendered in the Prometheus text exposition format.
+#[derive(Default)]
+struct Exposition {
+    interval: String,
+    warn: String,
+}
+
+
+// Serves the latest snapshot on `/metrics` for Prometheus to scrape, instead of sending it anywhere.
+pub struct PrometheusSink {
+    exposition: Arc<Mutex<Exposition>>,
+    address: SocketAddr,
+
+    // The background HTTP server, stopped when the sink is dropped (ex: its address changed on a config reload)
+    server: JoinHandle<()>,
+}
+
+
+impl PrometheusSink {
+    // Start the HTTP server in the background. Needs to be called from within a tokio runtime. Fails if the
+    // address can't be listened on (ex: its port is already in use).
+    pub fn new(log_credentials: &LogCredentials) -> Result<PrometheusSink, String> {
+        let address = parsers::get_address(log_credentials);
+        let listen_error = |err: std::io::Error| format!("Couldn't listen on the {} prometheus_address: {}", address, err);
+
+        let listener = std::net::TcpListener::bind(address).map_err(listen_error)?;
+        listener.set_nonblocking(true).map_err(listen_error)?;
+
+        let address = listener.local_addr().map_err(listen_error)?;
+        let listener = TcpListener::from_std(listener).map_err(listen_error)?;
+        let exposition = Arc::new(Mutex::new(Exposition::default()));
+
+        let server = tokio::spawn(serve(listener, exposition.clone()));
+
+        Ok(PrometheusSink {
+            exposition,
+            address,
+            server,
+        })
+    }
+
+
+    // The address the server listens on, useful when the configured port was 0.
+    pub fn address(&self) -> SocketAddr {
+        self.address
+    }
+}
+
+
+impl Drop for PrometheusSink {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+
+#[async_trait]
+impl Sink for PrometheusSink {
+    async fn emit_interval(&mut self, metrics: &IntervalMetrics, sample: &Sample<'_>) {
+        self.exposition.lock().unwrap().interval = render_interval(metrics, sample.system);
+    }
+
+
+    // The warnings are kept up to date by `update_warns`, which also sees them being cleared.
+    async fn emit_warn(&mut self, _metrics: &WarnMetrics, _sample: &Sample<'_>) {}
+
+
+    async fn update_warns(&mut self, metrics: &WarnMetrics, _sample: &Sample<'_>) {
+        self.exposition.lock().unwrap().warn = render_warns(metrics);
+    }
+}
+
+
+// Append a gauge with its help text and every one of its samples, as (labels, value).
+fn push_gauge(text: &mut String, name: &str, help: &str, samples: &[(&str, f64)]) {
+    writeln!(text, "# HELP server_watcher_{} {}", name, help).unwrap();
+    writeln!(text, "# TYPE server_watcher_{} gauge", name).unwrap();
+
+    for (labels, value) in samples {
+        writeln!(text, "server_watcher_{}{} {}", name, labels, value).unwrap();
+    }
+}
+
+
+// Render each enabled interval metric, keeping the units reported by the system.
+fn render_interval(metrics: &IntervalMetrics, system: &System) -> String {
+    let mut text = String::new();
+
+    if let Some(ram) = metrics.ram {
+        push_gauge(&mut text, "ram_used_kilobytes", "Used RAM.", &[("", ram as f64)]);
+        push_gauge(&mut text, "ram_total_kilobytes", "Total RAM.", &[("", system.get_total_memory() as f64)]);
+    }
+
+    if let Some(swap) = metrics.swap {
+        push_gauge(&mut text, "swap_used_kilobytes", "Used swap.", &[("", swap as f64)]);
+        push_gauge(&mut text, "swap_total_kilobytes", "Total swap.", &[("", system.get_total_swap() as f64)]);
+    }
+
+    if let Some(cpu) = metrics.cpu {
+        push_gauge(&mut text, "cpu_used_percent", "Used CPU, averaged over every core.", &[("", cpu as f64)]);
+    }
+
+    if let Some(cpu_cores) = &metrics.cpu_cores {
+        let labels = (0..cpu_cores.len())
+            .map(|core| format!("{{core=\"{}\"}}", core))
+            .collect::<Vec<String>>();
+
+        let samples = labels
+            .iter()
+            .zip(cpu_cores.iter())
+            .map(|(labels, used)| (labels.as_str(), *used as f64))
+            .collect::<Vec<(&str, f64)>>();
+
+        push_gauge(&mut text, "cpu_core_used_percent", "Used CPU of each core.", &samples);
+    }
+
+    if let Some((one, five, fifteen)) = metrics.cpu_average {
+        push_gauge(&mut text, "load_average", "System load average.", &[
+            ("{period=\"1m\"}", one),
+            ("{period=\"5m\"}", five),
+            ("{period=\"15m\"}", fifteen),
+        ]);
+    }
+
+    if let Some(system_uptime) = metrics.system_uptime {
+        push_gauge(&mut text, "uptime_seconds", "How long the system has been running for.", &[("", system_uptime as f64)]);
+    }
+
+    if let Some(disk) = &metrics.disk {
+        let labels = disk
+            .iter()
+            .map(|usage| format!("{{mount_point=\"{}\"}}", label_value(&usage.mount_point)))
+            .collect::<Vec<String>>();
+
+        let used_samples = labels
+            .iter()
+            .zip(disk.iter())
+            .map(|(labels, usage)| (labels.as_str(), usage.used as f64))
+            .collect::<Vec<(&str, f64)>>();
+
+        let total_samples = labels
+            .iter()
+            .zip(disk.iter())
+            .map(|(labels, usage)| (labels.as_str(), usage.total as f64))
+            .collect::<Vec<(&str, f64)>>();
+
+        push_gauge(&mut text, "disk_used_megabytes", "Used space of each watched disk.", &used_samples);
+        push_gauge(&mut text, "disk_total_megabytes", "Total space of each watched disk.", &total_samples);
+    }
+
+    if let Some(processes) = &metrics.processes {
+        let labels = processes
+            .iter()
+            .map(|status| format!("{{name=\"{}\"}}", label_value(&status.name)))
+            .collect::<Vec<String>>();
+
+        // Every gauge of a process shares its labels, only the value differs.
+        let samples = |value: fn(&ProcessStatus) -> f64| {
+            labels
+                .iter()
+                .zip(processes.iter())
+                .map(|(labels, status)| (labels.as_str(), value(status)))
+                .collect::<Vec<(&str, f64)>>()
+        };
+
+        push_gauge(&mut text, "process_running", "How many processes of each watched process are running.", &samples(|status| status.running as f64));
+        push_gauge(&mut text, "process_cpu_used_percent", "Used CPU of each watched process.", &samples(|status| status.cpu as f64));
+        push_gauge(&mut text, "process_memory_kilobytes", "Resident memory of each watched process.", &samples(|status| status.memory as f64));
+        push_gauge(&mut text, "process_threads", "Threads of each watched process.", &samples(|status| status.threads as f64));
+        push_gauge(&mut text, "process_uptime_seconds", "How long each watched process has been running for.", &samples(|status| status.uptime as f64));
+    }
+
+    if let Some(network) = &metrics.network {
+        let labels = network
+            .iter()
+            .map(|usage| format!("{{interface=\"{}\"}}", label_value(&usage.interface)))
+            .collect::<Vec<String>>();
+
+        let samples = |value: fn(&NetworkUsage) -> f64| {
+            labels
+                .iter()
+                .zip(network.iter())
+                .map(|(labels, usage)| (labels.as_str(), value(usage)))
+                .collect::<Vec<(&str, f64)>>()
+        };
+
+        push_gauge(&mut text, "network_receive_bytes_per_second", "Received data of each network interface.", &samples(|usage| usage.rx_bytes));
+        push_gauge(&mut text, "network_transmit_bytes_per_second", "Transmitted data of each network interface.", &samples(|usage| usage.tx_bytes));
+        push_gauge(&mut text, "network_receive_packets_per_second", "Received packets of each network interface.", &samples(|usage| usage.rx_packets));
+        push_gauge(&mut text, "network_transmit_packets_per_second", "Transmitted packets of each network interface.", &samples(|usage| usage.tx_packets));
+        push_gauge(&mut text, "network_receive_errors", "Receive errors of each network interface since the previous sample.", &samples(|usage| usage.rx_errors as f64));
+        push_gauge(&mut text, "network_transmit_errors", "Transmit errors of each network interface since the previous sample.", &samples(|usage| usage.tx_errors as f64));
+    }
+
+    if let Some(disk_io) = &metrics.disk_io {
+        let labels = disk_io
+            .iter()
+            .map(|usage| format!("{{device=\"{}\"}}", label_value(&usage.device)))
+            .collect::<Vec<String>>();
+
+        let samples = |value: fn(&DiskIoUsage) -> f64| {
+            labels
+                .iter()
+                .zip(disk_io.iter())
+                .map(|(labels, usage)| (labels.as_str(), value(usage)))
+                .collect::<Vec<(&str, f64)>>()
+        };
+
+        push_gauge(&mut text, "disk_io_read_bytes_per_second", "Read data of each block device.", &samples(|usage| usage.read_bytes));
+        push_gauge(&mut text, "disk_io_write_bytes_per_second", "Written data of each block device.", &samples(|usage| usage.write_bytes));
+        push_gauge(&mut text, "disk_io_reads_per_second", "Completed reads of each block device.", &samples(|usage| usage.read_iops));
+        push_gauge(&mut text, "disk_io_writes_per_second", "Completed writes of each block device.", &samples(|usage| usage.write_iops));
+        push_gauge(&mut text, "disk_io_latency_milliseconds", "Average time a read or write took on each block device.", &samples(|usage| usage.latency));
+        push_gauge(&mut text, "disk_io_utilisation_percent", "How much of the time each block device was busy.", &samples(|usage| usage.utilisation));
+    }
+
+    // Nothing is exported without sensors, so the gauges are missing instead of always 0.
+    if let Some(temperature) = metrics.temperature.as_ref().filter(|temperature| !temperature.is_empty()) {
+        let labels = temperature
+            .iter()
+            .map(|sensor| format!("{{component=\"{}\"}}", label_value(&sensor.label)))
+            .collect::<Vec<String>>();
+
+        let samples = |value: fn(&ComponentTemperature) -> Option<f32>| {
+            labels
+                .iter()
+                .zip(temperature.iter())
+                .filter_map(|(labels, sensor)| Some((labels.as_str(), value(sensor)? as f64)))
+                .collect::<Vec<(&str, f64)>>()
+        };
+
+        push_gauge(&mut text, "temperature_celsius", "Temperature of each hardware sensor.", &samples(|sensor| Some(sensor.current)));
+        push_gauge(&mut text, "temperature_max_celsius", "Highest temperature of each hardware sensor.", &samples(|sensor| Some(sensor.max)));
+
+        let critical_samples = samples(|sensor| sensor.critical);
+        if !critical_samples.is_empty() {
+            push_gauge(&mut text, "temperature_critical_celsius", "Critical temperature of each hardware sensor.", &critical_samples);
+        }
+    }
+
+    text
+}
+
+
+// Render the limit of each enabled warn metric, together with whether it is currently surpassed.
+// Mount points with their own disk limit get a separate sample, labeled with the mount point.
+// Each watched process tells whether it is currently down.
+fn render_warns(metrics: &WarnMetrics) -> String {
+    // Each enabled limit as (labels, lowest level, the severity it is surpassed with)
+    let mut limits = Vec::new();
+
+    let metric_limits = [
+        ("ram", metrics.ram),
+        ("cpu", metrics.cpu),
+        ("cpu_core", metrics.cpu_core),
+        ("disk", metrics.disk),
+        ("swap", metrics.swap),
+        ("disk_io", metrics.disk_io),
+    ];
+
+    for (metric, limit) in metric_limits.iter() {
+        if limit.lowest() == 0.0 { continue }
+
+        let active = metrics.warnings.iter().find(|warn| warn.metric_name() == *metric).map(Warn::severity);
+        limits.push((format!("{{metric=\"{}\"}}", metric), limit.lowest(), active));
+    }
+
+    for (mount_point, limit) in metrics.disk_mounts.iter() {
+        let limit = Limit::new(*limit, metrics.disk.critical).lowest();
+        if limit == 0.0 { continue }
+
+        let active = metrics.warnings
+            .iter()
+            .find(|warn| matches!(warn, Warn::HighDisk(_, warn_mount_point, _) if warn_mount_point == mount_point))
+            .map(Warn::severity);
+        limits.push((format!("{{metric=\"disk\",mount_point=\"{}\"}}", label_value(mount_point)), limit, active));
+    }
+
+    let mut text = String::new();
+
+    if !limits.is_empty() {
+        push_gauge(&mut text, "warn_limit_percent", "Configured warn limit.", &limit_samples(&limits));
+    }
+
+    // The limits which aren't percentages get a limit gauge for their own unit.
+    let absolute = &metrics.absolute_limits;
+    let unit_limits: [UnitLimits; 4] = [
+        ("warn_limit_mbits", "Configured bandwidth warn limit.", &[
+            ("network_rx", metrics.network_rx.lowest()),
+            ("network_tx", metrics.network_tx.lowest()),
+        ]),
+        ("warn_limit_celsius", "Configured temperature warn limit, or how close to its critical temperature a component may get.", &[
+            ("temperature", metrics.temperature),
+            ("temperature_critical", metrics.temperature_margin),
+        ]),
+        ("warn_limit_bytes", "Configured available space below which to warn.", &[
+            ("ram_free", absolute.ram_free_below as f64),
+            ("disk_free", absolute.disk_free_below as f64),
+        ]),
+        ("warn_limit_load", "Configured load average warn limit.", &[
+            ("load1", absolute.load1_above),
+            ("load5", absolute.load5_above),
+            ("load15", absolute.load15_above),
+        ]),
+    ];
+
+    for (gauge, help, metric_limits) in unit_limits.iter() {
+        let mut gauge_limits = Vec::new();
+
+        for (metric, limit) in metric_limits.iter() {
+            if *limit == 0.0 { continue }
+
+            let active = metrics.warnings.iter().find(|warn| warn.metric_name() == *metric).map(Warn::severity);
+            gauge_limits.push((format!("{{metric=\"{}\"}}", metric), *limit, active));
+        }
+
+        if !gauge_limits.is_empty() { push_gauge(&mut text, gauge, help, &limit_samples(&gauge_limits)) }
+        limits.extend(gauge_limits);
+    }
+
+    let warning_samples = limits
+        .iter()
+        .map(|(labels, _, active)| {
+            let value = match active {
+                None => 0.0,
+                Some(Severity::Warning) => 1.0,
+                Some(Severity::Critical) => 2.0,
+            };
+
+            (labels.as_str(), value)
+        })
+        .collect::<Vec<(&str, f64)>>();
+
+    if !warning_samples.is_empty() {
+        push_gauge(&mut text, "warning", "Whether the warn limit is currently surpassed, 1 for a warning and 2 when critical.", &warning_samples);
+    }
+
+    if !metrics.process_watchers.is_empty() {
+        let labels = metrics.process_watchers
+            .iter()
+            .map(|watcher| format!("{{name=\"{}\"}}", label_value(&watcher.name)))
+            .collect::<Vec<String>>();
+
+        let down_samples = labels
+            .iter()
+            .zip(metrics.process_watchers.iter())
+            .map(|(labels, watcher)| {
+                let down = metrics.warnings.iter().any(|warn| matches!(warn, Warn::ProcessDown(_, name) if *name == watcher.name));
+                (labels.as_str(), if down { 1.0 } else { 0.0 })
+            })
+            .collect::<Vec<(&str, f64)>>();
+
+        push_gauge(&mut text, "process_down", "Whether the watched process is currently not running.", &down_samples);
+    }
+
+    text
+}
+
+
+// A limit gauge which isn't a percentage, as (gauge name, help text, each (metric, limit))
+type UnitLimits<'a> = (&'a str, &'a str, &'a [(&'a str, f64)]);
+
+
+// Get the limit of each (labels, limit, the severity it is surpassed with) row.
+fn limit_samples(limits: &[(String, f64, Option<Severity>)]) -> Vec<(&str, f64)> {
+    limits
+        .iter()
+        .map(|(labels, limit, _)| (labels.as_str(), *limit))
+        .collect()
+}
+
+
+// Escape a label value, as described by the text exposition format.
+fn label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+
+// Accept connections until the process exits, answering each in its own task.
+async fn serve(listener: TcpListener, exposition: Arc<Mutex<Exposition>>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                tokio::spawn(respond(stream, exposition.clone()));
+            },
+            Err(err) => {
+                eprintln!("Couldn't accept a prometheus connection: {}", err);
+
+                // Errors like running out of file descriptors last a while, so they aren't retried right away.
+                tokio::time::sleep(ACCEPT_RETRY_DELAY).await;
+            },
+        }
+    }
+}
+
+
+async fn respond(mut stream: TcpStream, exposition: Arc<Mutex<Exposition>>) {
+    let (reader, mut writer) = stream.split();
+    let mut reader = BufReader::new(reader);
+    let mut request_line = String::new();
+
+    if reader.read_line(&mut request_line).await.is_err() { return }
+
+    // Skip the headers, the request line is enough to know what to answer.
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header).await {
+            Ok(read) if read > 0 && !header.trim().is_empty() => continue,
+            _ => break,
+        }
+    }
+
+    let (status, body) = if request_line.starts_with("GET /metrics ") {
+        let exposition = exposition.lock().unwrap();
+        ("200 OK", format!("{}{}", exposition.interval, exposition.warn))
+    } else {
+        ("404 Not Found", "Not Found\n".to_string())
+    };
+
+    let response = format!(
+        "HTTP/1.1 {}\r\nContent-Type: text/plain; version=0.0.4\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+        status, body.len(), body
+    );
+
+    if let Err(err) = writer.write_all(response.as_bytes()).await {
+        eprintln!("Couldn't answer a prometheus scrape: {}", err);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::logging::{Sink, Sample};
+    use crate::metrics::{MountUsage, interval::IntervalMetrics, process::{ProcessStatus, ProcessWatcher}, warn::{Limit, Severity, WarnMetrics, Warn}};
+    use crate::parse_config::{LogCredentials, ProcessWatch, ProcessMatcher};
+    use sysinfo::{System, SystemExt};
+    use super::{PrometheusSink, render_interval, render_warns};
+
+
+    #[test]
+    fn render_interval_renders_enabled() {
+        let system = System::new();
+        let metrics = IntervalMetrics {
+            cpu: Some(12.5),
+            cpu_average: Some((1.0, 0.5, 0.25)),
+            ..IntervalMetrics::default()
+        };
+
+        let text = render_interval(&metrics, &system);
+
+        assert_eq!(text, "\
+# HELP server_watcher_cpu_used_percent Used CPU, averaged over every core.
+# TYPE server_watcher_cpu_used_percent gauge
+server_watcher_cpu_used_percent 12.5
+# HELP server_watcher_load_average System load average.
+# TYPE server_watcher_load_average gauge
+server_watcher_load_average{period=\"1m\"} 1
+server_watcher_load_average{period=\"5m\"} 0.5
+server_watcher_load_average{period=\"15m\"} 0.25
+");
+    }
+
+
+    #[test]
+    fn render_interval_labels_mounts_and_processes() {
+        let system = System::new();
+        let metrics = IntervalMetrics {
+            disk: Some(vec![
+                MountUsage { mount_point: "/".into(), used: 2048, total: 4096 },
+                MountUsage { mount_point: "/mnt/\"quoted\"".into(), used: 10, total: 20 },
+            ]),
+            processes: Some(vec![
+                ProcessStatus { name: "web".into(), running: 2, cpu: 1.5, memory: 120_000, threads: 9, uptime: 3600 },
+            ]),
+            ..IntervalMetrics::default()
+        };
+
+        let text = render_interval(&metrics, &system);
+
+        assert!(text.contains("server_watcher_disk_used_megabytes{mount_point=\"/\"} 2048\n"));
+        assert!(text.contains("server_watcher_disk_total_megabytes{mount_point=\"/\"} 4096\n"));
+        assert!(text.contains("server_watcher_disk_used_megabytes{mount_point=\"/mnt/\\\"quoted\\\"\"} 10\n"));
+        assert!(text.contains("server_watcher_process_running{name=\"web\"} 2\n"));
+        assert!(text.contains("server_watcher_process_threads{name=\"web\"} 9\n"));
+    }
+
+
+    #[test]
+    fn render_warns_renders_active_and_cleared() {
+        let metrics = WarnMetrics {
+            ram: Limit::new(80.0, 0.0),
+            cpu: Limit::new(90.0, 0.0),
+            disk_mounts: vec![("/var".into(), 90.0), ("/boot".into(), 95.0)],
+            process_watchers: vec![
+                ProcessWatcher::new(&ProcessWatch { name: "web".into(), matcher: ProcessMatcher::Name("nginx".into()) }),
+                ProcessWatcher::new(&ProcessWatch { name: "db".into(), matcher: ProcessMatcher::Name("postgres".into()) }),
+            ],
+            network_rx: Limit::new(100.0, 0.0),
+            temperature_margin: 5.0,
+            warnings: vec![
+                Warn::HighCPU(Severity::Warning, 95.0, vec![]),
+                Warn::HighDisk(Severity::Warning, "/var".into(), 92.0),
+                Warn::ProcessDown(Severity::Warning, "db".into()),
+                Warn::HighNetworkRX(Severity::Warning, "eth0".into(), 120.0),
+            ],
+            ..WarnMetrics::default()
+        };
+
+        let text = render_warns(&metrics);
+
+        assert!(text.contains("server_watcher_warn_limit_percent{metric=\"ram\"} 80\n"));
+        assert!(text.contains("server_watcher_warning{metric=\"ram\"} 0\n"));
+        assert!(text.contains("server_watcher_warning{metric=\"cpu\"} 1\n"));
+        assert!(text.contains("server_watcher_warning{metric=\"disk\",mount_point=\"/var\"} 1\n"));
+        assert!(text.contains("server_watcher_warning{metric=\"disk\",mount_point=\"/boot\"} 0\n"));
+        assert!(!text.contains("{metric=\"disk\"}"));
+        assert!(text.contains("server_watcher_process_down{name=\"web\"} 0\n"));
+        assert!(text.contains("server_watcher_process_down{name=\"db\"} 1\n"));
+        assert!(text.contains("server_watcher_warn_limit_mbits{metric=\"network_rx\"} 100\n"));
+        assert!(text.contains("server_watcher_warning{metric=\"network_rx\"} 1\n"));
+        assert!(!text.contains("network_tx"));
+        assert!(text.contains("server_watcher_warn_limit_celsius{metric=\"temperature_critical\"} 5\n"));
+        assert!(text.contains("server_watcher_warning{metric=\"temperature_critical\"} 0\n"));
+        assert_eq!(text.matches("# TYPE server_watcher_warning gauge").count(), 1);
+    }
+
+
+    #[tokio::test]
+    async fn serves_latest_metrics() {
+        let mut sink = PrometheusSink::new(&LogCredentials::PrometheusLog {
+            address: "127.0.0.1:0".parse().unwrap()
+        }).unwrap();
+        let system = System::new();
+        let metrics = IntervalMetrics {
+            system_uptime: Some(600),
+            ..IntervalMetrics::default()
+        };
+
+        sink.emit_interval(&metrics, &Sample::new(&system, "test-host", None)).await;
+
+        let base_url = format!("http://{}", sink.address());
+        let response = reqwest::get(format!("{}/metrics", base_url)).await.unwrap();
+        assert!(response.status().is_success());
+        assert!(response.text().await.unwrap().contains("server_watcher_uptime_seconds 600\n"));
+
+        let response = reqwest::get(format!("{}/other", base_url)).await.unwrap();
+        assert_eq!(response.status().as_u16(), 404);
+    }
+
+
+    #[tokio::test]
+    async fn new_fails_on_used_address() {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+
+        let err = PrometheusSink::new(&LogCredentials::PrometheusLog { address }).err().unwrap();
+
+        assert!(err.starts_with(&format!("Couldn't listen on the {} prometheus_address", address)));
+    }
+}
@@ -0,0 +1,40 @@
+use crate::parse_config::LogCredentials;
+use std::net::SocketAddr;
+
+
+pub fn get_address(log_credentials: &LogCredentials) -> SocketAddr {
+    match log_credentials {
+        LogCredentials::PrometheusLog { address } => *address,
+        _ => panic!("The logging mode is not set to prometheus logging.")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_config::{LogCredentials, FileFormat, FileRotation};
+    use super::get_address;
+
+
+    #[test]
+    fn get_address_gets_address() {
+        let credentials = LogCredentials::PrometheusLog {
+            address: "127.0.0.1:9100".parse().unwrap()
+        };
+
+        assert_eq!(get_address(&credentials), "127.0.0.1:9100".parse().unwrap());
+    }
+
+
+    #[test]
+    #[should_panic = "The logging mode is not set to prometheus logging."]
+    fn get_address_wrong_logging_mode() {
+        let credentials = LogCredentials::FileLog {
+            path: "C:/special/path".into(),
+            format: FileFormat::Text,
+            rotation: FileRotation::default(),
+        };
+
+        get_address(&credentials);
+    }
+}
@@ -0,0 +1,201 @@
+use crate::metrics::{interval::IntervalMetrics, warn::{Severity, WarnMetrics}};
+use crate::parse_config::LogCredentials;
+use super::{Sink, Sample, format, format::AlertGroup, webhook::WebhookSink};
+use async_trait::async_trait;
+use serde_json::{json, Value};
+
+
+mod parsers;
+
+
+// Slack allows at most 10 fields in a single section block.
+const MAX_SECTION_FIELDS: usize = 10;
+
+
+// Sends every snapshot to a Slack incoming webhook, as an attachment with the same fields and
+// colours as the Discord embeds.
+pub struct SlackSink {
+    webhook: WebhookSink,
+
+    // Where the critical alerts are sent instead, if set
+    critical_webhook: Option<WebhookSink>,
+}
+
+
+impl SlackSink {
+    pub fn new(log_credentials: &LogCredentials) -> SlackSink {
+        let (url, critical_url) = parsers::get_urls(log_credentials);
+
+        SlackSink {
+            webhook: incoming_webhook(url),
+            critical_webhook: critical_url.map(incoming_webhook),
+        }
+    }
+}
+
+
+// An incoming webhook is a plain webhook which expects Slack's own payload.
+fn incoming_webhook(url: String) -> WebhookSink {
+    WebhookSink::new(&LogCredentials::WebhookLog {
+        url,
+        headers: vec![],
+        timeout: 10,
+        retries: 3,
+    })
+}
+
+
+#[async_trait]
+impl Sink for SlackSink {
+    async fn emit_interval(&mut self, metrics: &IntervalMetrics, sample: &Sample<'_>) {
+        let fields = format::interval_fields(metrics, sample.system);
+        let payload = slack_payload("Server Interval Metrics", "#00bedb", fields, &format::identity(sample));
+
+        if !self.webhook.post(&payload).await {
+            eprintln!("Giving up on sending the interval metrics to slack");
+        }
+    }
+
+
+    // The firing critical alerts, the firing warnings and the resolved alerts are sent as separate red, orange
+    // and green attachments. The critical ones go to the critical webhook if there is one.
+    async fn emit_warn(&mut self, metrics: &WarnMetrics, sample: &Sample<'_>) {
+        for (group, alerts) in format::group_alerts(&metrics.alerts) {
+            let fields = alerts
+                .iter()
+                .flat_map(|alert| format::alert_fields(alert, sample.system))
+                .collect();
+            let payload = slack_payload(group.title(), group_color(group), fields, &format::identity(sample));
+
+            let webhook = match (group.severity(), &self.critical_webhook) {
+                (Severity::Critical, Some(critical_webhook)) => critical_webhook,
+                _ => &self.webhook,
+            };
+
+            if !webhook.post(&payload).await {
+                eprintln!("Giving up on sending the warn metrics to slack");
+            }
+        }
+    }
+}
+
+
+fn group_color(group: AlertGroup) -> &'static str {
+    match group {
+        AlertGroup::Firing(Severity::Critical) => "#c50000",
+        AlertGroup::Firing(Severity::Warning) => "#e67e22",
+        AlertGroup::Resolved(_) => "#00a046",
+    }
+}
+
+
+// Put the fields in a coloured Block Kit attachment, under a header with the passed title and
+// above a small context line telling where the metrics are from.
+fn slack_payload(title: &str, color: &str, fields: Vec<(&'static str, String)>, context: &str) -> Value {
+    let mut blocks = vec![json!({
+        "type": "header",
+        "text": { "type": "plain_text", "text": title },
+    })];
+
+    let fields = fields
+        .into_iter()
+        .map(|(name, value)| json!({ "type": "mrkdwn", "text": format!("*{}*\n{}", name, value) }))
+        .collect::<Vec<Value>>();
+
+    for section_fields in fields.chunks(MAX_SECTION_FIELDS) {
+        blocks.push(json!({ "type": "section", "fields": section_fields }));
+    }
+
+    blocks.push(json!({
+        "type": "context",
+        "elements": [{ "type": "plain_text", "text": context }],
+    }));
+
+    json!({
+        // Shown in notifications, where the attachment itself isn't rendered.
+        "text": title,
+        "attachments": [{ "color": color, "blocks": blocks }],
+    })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::logging::{Sink, Sample, test_server::serve};
+    use crate::metrics::{alert::{Alert, AlertState}, warn::{Limit, Severity, WarnMetrics, Warn}};
+    use crate::parse_config::LogCredentials;
+    use serde_json::json;
+    use std::time::Duration;
+    use sysinfo::{System, SystemExt};
+    use super::{SlackSink, slack_payload};
+
+
+    #[test]
+    fn slack_payload_creates_attachment() {
+        let payload = slack_payload("Server Interval Metrics", "#00bedb", vec![
+            ("Used CPU", "12.50%".to_string()),
+            ("Used Disk Space", "2048 MB".to_string()),
+        ], "host.example - 2021-06-01T10:00:00Z");
+
+        assert_eq!(payload, json!({
+            "text": "Server Interval Metrics",
+            "attachments": [{
+                "color": "#00bedb",
+                "blocks": [
+                    { "type": "header", "text": { "type": "plain_text", "text": "Server Interval Metrics" } },
+                    { "type": "section", "fields": [
+                        { "type": "mrkdwn", "text": "*Used CPU*\n12.50%" },
+                        { "type": "mrkdwn", "text": "*Used Disk Space*\n2048 MB" },
+                    ]},
+                    { "type": "context", "elements": [
+                        { "type": "plain_text", "text": "host.example - 2021-06-01T10:00:00Z" },
+                    ]},
+                ],
+            }],
+        }));
+    }
+
+
+    #[test]
+    fn slack_payload_splits_sections() {
+        let fields = (0..12).map(|_| ("Used CPU", "12.50%".to_string())).collect();
+        let payload = slack_payload("Server Interval Metrics", "#00bedb", fields, "host.example");
+        let blocks = payload["attachments"][0]["blocks"].as_array().unwrap();
+
+        assert_eq!(blocks.len(), 4);
+        assert_eq!(blocks[1]["fields"].as_array().unwrap().len(), 10);
+        assert_eq!(blocks[2]["fields"].as_array().unwrap().len(), 2);
+    }
+
+
+    #[tokio::test]
+    async fn emit_warn_posts_firing_and_resolved_attachments() {
+        let (url, server) = serve(vec![200, 200]);
+        let mut sink = SlackSink::new(&LogCredentials::SlackLog { url, critical_url: None });
+        let system = System::new();
+        let metrics = WarnMetrics {
+            cpu: Limit::new(10.0, 0.0),
+            alerts: vec![
+                Alert { state: AlertState::Resolved, warn: Warn::HighSwap(Severity::Warning, 90.0), duration: Duration::from_secs(600) },
+                Alert { state: AlertState::Firing, warn: Warn::HighCPU(Severity::Critical, 70.0, vec![]), duration: Duration::from_secs(0) },
+            ],
+            warnings: vec![Warn::HighCPU(Severity::Critical, 70.0, vec![])],
+            ..WarnMetrics::default()
+        };
+
+        sink.emit_warn(&metrics, &Sample::new(&system, "test-host", Some("web-1"))).await;
+
+        let requests = server.join().unwrap();
+        let (_, firing) = &requests[0];
+        let (_, resolved) = &requests[1];
+        let context = firing["attachments"][0]["blocks"][2]["elements"][0]["text"].as_str().unwrap();
+
+        assert_eq!(firing["text"], "Server Critical Firing");
+        assert_eq!(firing["attachments"][0]["color"], "#c50000");
+        assert_eq!(firing["attachments"][0]["blocks"][1]["fields"][0]["text"], "*High CPU Usage*\n70.00%");
+        assert!(context.starts_with("web-1 (test-host) - "));
+        assert_eq!(resolved["text"], "Server Warn Resolved");
+        assert_eq!(resolved["attachments"][0]["color"], "#00a046");
+        assert_eq!(resolved["attachments"][0]["blocks"][1]["fields"][0]["text"], "*High Swap Usage*\nBack to normal after 10 minutes");
+    }
+}
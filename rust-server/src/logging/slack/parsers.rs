@@ -0,0 +1,41 @@
+use crate::parse_config::LogCredentials;
+
+
+// Get the incoming webhook URL, together with the one the critical alerts are sent to instead.
+pub fn get_urls(log_credentials: &LogCredentials) -> (String, Option<String>) {
+    match log_credentials {
+        LogCredentials::SlackLog { url, critical_url } => (url.clone(), critical_url.clone()),
+        _ => panic!("The logging mode is not set to slack logging.")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_config::{LogCredentials, FileFormat, FileRotation};
+    use super::get_urls;
+
+
+    #[test]
+    fn get_urls_gets_urls() {
+        let credentials = LogCredentials::SlackLog {
+            url: "https://hooks.slack.com/services/T000/B000/XXXX".into(),
+            critical_url: None
+        };
+
+        assert_eq!(get_urls(&credentials), ("https://hooks.slack.com/services/T000/B000/XXXX".to_string(), None));
+    }
+
+
+    #[test]
+    #[should_panic = "The logging mode is not set to slack logging."]
+    fn get_urls_wrong_logging_mode() {
+        let credentials = LogCredentials::FileLog {
+            path: "C:/special/path".into(),
+            format: FileFormat::Text,
+            rotation: FileRotation::default(),
+        };
+
+        get_urls(&credentials);
+    }
+}
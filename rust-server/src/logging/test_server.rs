@@ -0,0 +1,46 @@
+// A tiny HTTP server for testing the sinks which post JSON to an URL.
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::thread::JoinHandle;
+
+
+// The header lines and the JSON body of a request received by the test server.
+pub type ReceivedRequest = (Vec<String>, Value);
+
+
+// Answer the passed statuses in order, one per connection, and return each received request.
+pub fn serve(statuses: Vec<u16>) -> (String, JoinHandle<Vec<ReceivedRequest>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let url = format!("http://{}/hook", listener.local_addr().unwrap());
+
+    let handle = std::thread::spawn(move || {
+        statuses.into_iter().map(|status| {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut headers = Vec::new();
+            let mut content_length = 0;
+
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.trim_end().to_string();
+                if line.is_empty() { break }
+
+                if let Some(length) = line.to_lowercase().strip_prefix("content-length:") {
+                    content_length = length.trim().parse().unwrap();
+                }
+                headers.push(line);
+            }
+
+            let mut body = vec![0; content_length];
+            reader.read_exact(&mut body).unwrap();
+            write!(reader.get_mut(), "HTTP/1.1 {} OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n", status).unwrap();
+
+            (headers, serde_json::from_slice(&body).unwrap())
+        })
+        .collect()
+    });
+
+    (url, handle)
+}
@@ -0,0 +1,162 @@
+use crate::metrics::{interval::IntervalMetrics, warn::WarnMetrics};
+use crate::parse_config::LogCredentials;
+use super::{Sink, Sample, format};
+use async_trait::async_trait;
+use reqwest::{Client, header::HeaderMap};
+use serde_json::Value;
+use std::time::Duration;
+
+
+mod parsers;
+
+
+// How long to wait before the first retry, doubled after every failed attempt.
+const FIRST_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+
+// Posts every snapshot as a JSON document to a generic HTTP webhook.
+pub struct WebhookSink {
+    client: Client,
+    url: String,
+    headers: HeaderMap,
+    retries: u32,
+    first_retry_delay: Duration,
+}
+
+
+impl WebhookSink {
+    pub fn new(log_credentials: &LogCredentials) -> WebhookSink {
+        let (url, headers, timeout, retries) = parsers::parse_webhook(log_credentials);
+        let client = Client::builder()
+            .timeout(Duration::from_secs(timeout as u64))
+            .build()
+            .expect("Couldn't create the webhook HTTP client");
+
+        WebhookSink {
+            client,
+            url,
+            headers,
+            retries,
+            first_retry_delay: FIRST_RETRY_DELAY,
+        }
+    }
+
+
+    // Post the payload, retrying with a growing delay if the request fails or isn't accepted.
+    // Returns whether the webhook accepted the payload in the end.
+    pub(crate) async fn post(&self, payload: &Value) -> bool {
+        let mut retry_delay = self.first_retry_delay;
+
+        for attempt in 0..=self.retries {
+            if attempt > 0 {
+                tokio::time::sleep(retry_delay).await;
+                retry_delay *= 2;
+            }
+
+            let response = self.client
+                .post(&self.url)
+                .headers(self.headers.clone())
+                .json(payload)
+                .send()
+                .await;
+
+            match response {
+                Ok(response) if response.status().is_success() => return true,
+                Ok(response) => eprintln!("The webhook responded with {}", response.status()),
+                Err(err) => eprintln!("Couldn't send the metrics to the webhook: {}", error_text(&err)),
+            }
+        }
+
+        false
+    }
+}
+
+
+// The error without the URL it was for, which can have a token in it.
+fn error_text(err: &reqwest::Error) -> String {
+    match err.url() {
+        Some(url) => err.to_string().replace(&format!(" for url ({})", url.as_str()), ""),
+        None => err.to_string(),
+    }
+}
+
+
+#[async_trait]
+impl Sink for WebhookSink {
+    async fn emit_interval(&mut self, metrics: &IntervalMetrics, sample: &Sample<'_>) {
+        let payload = format::record_json(sample, "interval", "metrics", format::interval_json(metrics, sample.system));
+
+        if !self.post(&payload).await {
+            eprintln!("Giving up on sending the interval metrics to the webhook");
+        }
+    }
+
+
+    async fn emit_warn(&mut self, metrics: &WarnMetrics, sample: &Sample<'_>) {
+        let payload = format::record_json(sample, "warn", "alerts", format::alert_json(&metrics.alerts));
+
+        if !self.post(&payload).await {
+            eprintln!("Giving up on sending the warn metrics to the webhook");
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::logging::test_server::serve;
+    use crate::parse_config::LogCredentials;
+    use serde_json::json;
+    use std::time::Duration;
+    use super::WebhookSink;
+
+
+    fn sink(url: String, retries: u32) -> WebhookSink {
+        let mut sink = WebhookSink::new(&LogCredentials::WebhookLog {
+            url,
+            headers: vec![("Authorization".into(), "Bearer token".into())],
+            timeout: 5,
+            retries,
+        });
+        sink.first_retry_delay = Duration::from_millis(10);
+
+        sink
+    }
+
+
+    #[tokio::test]
+    async fn post_sends_json_with_headers() {
+        let (url, server) = serve(vec![200]);
+        let sink = sink(url, 0);
+
+        assert!(sink.post(&json!({ "mode": "interval" })).await);
+
+        let requests = server.join().unwrap();
+        let (headers, body) = &requests[0];
+
+        assert!(headers[0].starts_with("POST /hook"));
+        assert!(headers.iter().any(|header| header.eq_ignore_ascii_case("authorization: Bearer token")));
+        assert!(headers.iter().any(|header| header.eq_ignore_ascii_case("content-type: application/json")));
+        assert_eq!(body, &json!({ "mode": "interval" }));
+    }
+
+
+    #[tokio::test]
+    async fn post_retries_failed_requests() {
+        let (url, server) = serve(vec![500, 503, 200]);
+        let sink = sink(url, 2);
+
+        assert!(sink.post(&json!({ "mode": "warn" })).await);
+        assert_eq!(server.join().unwrap().len(), 3);
+    }
+
+
+    #[tokio::test]
+    async fn post_gives_up_after_retries() {
+        let (url, server) = serve(vec![500, 500]);
+        let sink = sink(url, 1);
+
+        assert!(!sink.post(&json!({ "mode": "warn" })).await);
+        assert_eq!(server.join().unwrap().len(), 2);
+    }
+}
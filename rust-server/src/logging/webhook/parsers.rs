@@ -0,0 +1,81 @@
+use crate::parse_config::LogCredentials;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+
+
+// Turn the configured header pairs into headers which can be sent with every request. The config already rejects
+// the headers which aren't valid, so any left are skipped.
+pub fn parse_headers(headers: &[(String, String)]) -> HeaderMap {
+    let mut header_map = HeaderMap::new();
+
+    for (name, value) in headers {
+        if let (Ok(name), Ok(value)) = (HeaderName::from_bytes(name.as_bytes()), HeaderValue::from_str(value)) {
+            header_map.insert(name, value);
+        }
+    }
+
+    header_map
+}
+
+
+pub fn parse_webhook(log_credentials: &LogCredentials) -> (String, HeaderMap, u32, u32) {
+    match log_credentials {
+        LogCredentials::WebhookLog { url, headers, timeout, retries } => {
+            (url.clone(), parse_headers(headers), *timeout, *retries)
+        },
+        _ => panic!("The logging mode is not set to webhook logging.")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_config::{LogCredentials, FileFormat, FileRotation};
+    use super::parse_webhook;
+
+
+    #[test]
+    fn parse_webhook_parses() {
+        let credentials = LogCredentials::WebhookLog {
+            url: "http://127.0.0.1:8080/hook".into(),
+            headers: vec![("Authorization".into(), "Bearer token".into())],
+            timeout: 5,
+            retries: 2,
+        };
+
+        let (url, headers, timeout, retries) = parse_webhook(&credentials);
+
+        assert_eq!(url, "http://127.0.0.1:8080/hook");
+        assert_eq!(headers.get("authorization").unwrap(), "Bearer token");
+        assert_eq!(timeout, 5);
+        assert_eq!(retries, 2);
+    }
+
+
+    #[test]
+    fn parse_webhook_skips_invalid_header() {
+        let credentials = LogCredentials::WebhookLog {
+            url: "http://127.0.0.1:8080/hook".into(),
+            headers: vec![("Not A Header".into(), "value".into()), ("X-Source".into(), "watcher".into())],
+            timeout: 5,
+            retries: 2,
+        };
+
+        let (_, headers, _, _) = parse_webhook(&credentials);
+
+        assert_eq!(headers.len(), 1);
+        assert_eq!(headers.get("x-source").unwrap(), "watcher");
+    }
+
+
+    #[test]
+    #[should_panic = "The logging mode is not set to webhook logging."]
+    fn parse_webhook_wrong_logging_mode() {
+        let credentials = LogCredentials::FileLog {
+            path: "C:/special/path".into(),
+            format: FileFormat::Text,
+            rotation: FileRotation::default(),
+        };
+
+        parse_webhook(&credentials);
+    }
+}
@@ -1,11 +1,30 @@
-use env_plus::EnvLoader;
-use rust_server;
+use rust_server::cli::{self, Command};
 
 fn main() {
-    EnvLoader::new()
-    .change_file(String::from("./.env"))
-    .change_comment(String::from("#"))
-    .activate();
+    let args = std::env::args().skip(1).collect::<Vec<String>>();
+    let cli = match cli::parse_args(&args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, cli::USAGE);
+            std::process::exit(2);
+        },
+    };
 
-    rust_server::start();
+    // Help is printed without the config, every other command loads the files first.
+    match cli.command {
+        Command::Help => println!("{}", cli::USAGE),
+        Command::Run => rust_server::run(cli::load_vars(&cli), Vec::new()),
+        Command::Check => {
+            cli::load_vars(&cli);
+            std::process::exit(cli::check());
+        },
+        Command::Once(format) => {
+            cli::load_vars(&cli);
+            cli::once(&rust_server::config_or_exit(), format);
+        },
+        Command::PrintConfig => {
+            cli::load_vars(&cli);
+            cli::print_config(&rust_server::config_or_exit());
+        },
+    }
 }
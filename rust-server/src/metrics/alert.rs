@@ -0,0 +1,186 @@
+use super::warn::Warn;
+use std::time::{Duration, Instant};
+
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AlertState {
+    // The warning was just reported
+    Firing,
+
+    // The warning is still reported, sent again as it has been a while since it was last sent
+    Reminder,
+
+    // The warning isn't reported anymore
+    Resolved,
+}
+
+
+impl AlertState {
+    pub fn name(&self) -> &'static str {
+        match self {
+            AlertState::Firing => "firing",
+            AlertState::Reminder => "reminder",
+            AlertState::Resolved => "resolved",
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// A change of a warning which the sinks should be told about
+pub struct Alert {
+    pub state: AlertState,
+
+    // The latest warning, a resolved alert keeps the last values it was reported with
+    pub warn: Warn,
+
+    // How long the warning has been reported for
+    pub duration: Duration,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+struct FiringAlert {
+    key: String,
+    warn: Warn,
+    since: Instant,
+    last_sent: Instant,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// Remembers which warnings are firing, so each one is only sent when it starts, is reminded of and resolves,
+// instead of on every run of the warn mode.
+pub struct AlertTracker {
+    // How often a firing warning is sent again, None to only send it once
+    reminder: Option<Duration>,
+
+    firing: Vec<FiringAlert>,
+}
+
+
+impl AlertTracker {
+    // A reminder of 0 minutes disables the reminders.
+    pub fn new(reminder_minutes: u32) -> AlertTracker {
+        AlertTracker {
+            reminder: if reminder_minutes > 0 { Some(Duration::from_secs(reminder_minutes as u64 * 60)) } else { None },
+            firing: vec![],
+        }
+    }
+
+
+    // Keep the firing warnings of the tracker used before the config was reloaded, so they aren't sent again.
+    pub fn keep_state(&mut self, previous: AlertTracker) {
+        self.firing = previous.firing;
+    }
+
+
+    // Compare the warnings of this run against the firing ones. Returns the alerts which started, changed severity
+    // or are due a reminder in the order of the warnings, followed by the ones which resolved.
+    pub fn update(&mut self, warnings: &[Warn], now: Instant) -> Vec<Alert> {
+        let mut alerts = Vec::new();
+        let mut still_firing = Vec::new();
+
+        for warn in warnings {
+            let key = warn.key();
+
+            let firing = match self.firing.iter().position(|firing| firing.key == key) {
+                Some(index) => {
+                    let mut firing = self.firing.remove(index);
+                    let severity_changed = firing.warn.severity() != warn.severity();
+                    firing.warn = warn.clone();
+
+                    // A warning which became critical, or went back to a warning, is sent again as firing.
+                    if severity_changed {
+                        firing.last_sent = now;
+                        alerts.push(Alert { state: AlertState::Firing, warn: warn.clone(), duration: now.duration_since(firing.since) });
+                    } else if self.reminder.is_some_and(|reminder| now.duration_since(firing.last_sent) >= reminder) {
+                        firing.last_sent = now;
+                        alerts.push(Alert { state: AlertState::Reminder, warn: warn.clone(), duration: now.duration_since(firing.since) });
+                    }
+
+                    firing
+                },
+                None => {
+                    alerts.push(Alert { state: AlertState::Firing, warn: warn.clone(), duration: Duration::from_secs(0) });
+                    FiringAlert { key, warn: warn.clone(), since: now, last_sent: now }
+                },
+            };
+
+            still_firing.push(firing);
+        }
+
+        // Whatever is left wasn't reported on this run.
+        alerts.extend(self.firing.drain(..).map(|firing| Alert {
+            state: AlertState::Resolved,
+            duration: now.duration_since(firing.since),
+            warn: firing.warn,
+        }));
+
+        self.firing = still_firing;
+        alerts
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::warn::{Severity, Warn};
+    use std::time::{Duration, Instant};
+    use super::{Alert, AlertState, AlertTracker};
+
+
+    #[test]
+    fn update_sends_firing_and_resolved_once() {
+        let mut tracker = AlertTracker::new(0);
+        let start = Instant::now();
+
+        let first = tracker.update(&[Warn::HighSwap(Severity::Warning, 90.0)], start);
+        let second = tracker.update(&[Warn::HighSwap(Severity::Warning, 95.0)], start + Duration::from_secs(60));
+        let third = tracker.update(&[], start + Duration::from_secs(90));
+        let fourth = tracker.update(&[], start + Duration::from_secs(120));
+
+        assert_eq!(first, vec![Alert { state: AlertState::Firing, warn: Warn::HighSwap(Severity::Warning, 90.0), duration: Duration::from_secs(0) }]);
+        assert_eq!(second, vec![]);
+        assert_eq!(third, vec![Alert { state: AlertState::Resolved, warn: Warn::HighSwap(Severity::Warning, 95.0), duration: Duration::from_secs(90) }]);
+        assert_eq!(fourth, vec![]);
+    }
+
+
+    #[test]
+    fn update_sends_reminders() {
+        let mut tracker = AlertTracker::new(10);
+        let start = Instant::now();
+        let disk = |used: f32| Warn::HighDisk(Severity::Warning, "/".into(), used);
+
+        tracker.update(&[disk(90.0)], start);
+        let early = tracker.update(&[disk(91.0)], start + Duration::from_secs(300));
+        let due = tracker.update(&[disk(92.0), Warn::HighSwap(Severity::Warning, 90.0)], start + Duration::from_secs(600));
+        let after_reminder = tracker.update(&[disk(93.0)], start + Duration::from_secs(900));
+
+        assert_eq!(early, vec![]);
+        assert_eq!(due, vec![
+            Alert { state: AlertState::Reminder, warn: disk(92.0), duration: Duration::from_secs(600) },
+            Alert { state: AlertState::Firing, warn: Warn::HighSwap(Severity::Warning, 90.0), duration: Duration::from_secs(0) },
+        ]);
+        assert_eq!(after_reminder, vec![
+            Alert { state: AlertState::Resolved, warn: Warn::HighSwap(Severity::Warning, 90.0), duration: Duration::from_secs(300) },
+        ]);
+    }
+
+
+    #[test]
+    fn update_sends_severity_changes() {
+        let mut tracker = AlertTracker::new(0);
+        let start = Instant::now();
+
+        tracker.update(&[Warn::HighRAM(Severity::Warning, 85.0, vec![])], start);
+        let critical = tracker.update(&[Warn::HighRAM(Severity::Critical, 96.0, vec![])], start + Duration::from_secs(60));
+        let still_critical = tracker.update(&[Warn::HighRAM(Severity::Critical, 97.0, vec![])], start + Duration::from_secs(120));
+
+        assert_eq!(critical, vec![
+            Alert { state: AlertState::Firing, warn: Warn::HighRAM(Severity::Critical, 96.0, vec![]), duration: Duration::from_secs(60) },
+        ]);
+        assert_eq!(still_critical, vec![]);
+    }
+}
@@ -0,0 +1,206 @@
+use std::path::Path;
+use std::time::Instant;
+
+
+// The size of a sector in /proc/diskstats, no matter the actual sector size of the device.
+const SECTOR_SIZE: u64 = 512;
+
+// Devices which are not real disks, skipped even though they are listed as whole devices.
+const SKIPPED_PREFIXES: [&str; 3] = ["loop", "ram", "zram"];
+
+
+#[derive(Debug, Clone, PartialEq)]
+// The counters of a single block device from /proc/diskstats, as totals since boot
+struct DiskIoCounters {
+    reads: u64,
+    read_sectors: u64,
+    read_ms: u64,
+    writes: u64,
+    write_sectors: u64,
+    write_ms: u64,
+
+    // How long the device had at least one I/O in flight [In ms]
+    busy_ms: u64,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// The I/O of a single block device between two samples
+pub struct DiskIoUsage {
+    pub device: String,
+
+    // Read and written data [In bytes per second]
+    pub read_bytes: f64,
+    pub write_bytes: f64,
+
+    // Completed reads and writes [Per second]
+    pub read_iops: f64,
+    pub write_iops: f64,
+
+    // How long a read or write took on average [In ms]
+    pub latency: f64,
+
+    // How much of the time the device was busy [%]
+    pub utilisation: f64,
+}
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// Remembers the counters of each block device from the previous sample, so the I/O covers the
+// whole time since the mode last ran.
+pub struct DiskIoTracker {
+    // When the previous sample was taken, together with the counters of each device
+    previous: Option<(Instant, Vec<(String, DiskIoCounters)>)>,
+}
+
+
+impl DiskIoTracker {
+    // Get the I/O of each block device since the previous call, in the order of /proc/diskstats. The first
+    // call only remembers the counters and returns nothing. Without /proc/diskstats (ex: not on linux) nothing is returned.
+    pub fn usage(&mut self) -> Vec<DiskIoUsage> {
+        let counters = std::fs::read_to_string("/proc/diskstats")
+            .map(|diskstats| parse_diskstats(&diskstats))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|(device, _)| is_whole_device(device))
+            .collect();
+
+        self.usage_since(counters, Instant::now())
+    }
+
+
+    fn usage_since(&mut self, counters: Vec<(String, DiskIoCounters)>, now: Instant) -> Vec<DiskIoUsage> {
+        let usage = match &self.previous {
+            Some((taken_at, previous)) => {
+                let seconds = now.duration_since(*taken_at).as_secs_f64();
+
+                counters
+                .iter()
+                .filter_map(|(device, current)| {
+                    let (_, previous) = previous.iter().find(|(previous_device, _)| previous_device == device)?;
+                    Some(device_usage(device, previous, current, seconds))
+                })
+                .collect()
+            },
+            None => vec![],
+        };
+
+        self.previous = Some((now, counters));
+        usage
+    }
+}
+
+
+// Get the counters of each device listed in /proc/diskstats. Lines which can't be parsed are skipped.
+fn parse_diskstats(diskstats: &str) -> Vec<(String, DiskIoCounters)> {
+    diskstats
+    .lines()
+    .filter_map(|line| {
+        let fields = line.split_whitespace().collect::<Vec<&str>>();
+        if fields.len() < 13 { return None }
+
+        let field = |index: usize| fields[index].parse::<u64>().ok();
+
+        Some((fields[2].to_string(), DiskIoCounters {
+            reads: field(3)?,
+            read_sectors: field(5)?,
+            read_ms: field(6)?,
+            writes: field(7)?,
+            write_sectors: field(9)?,
+            write_ms: field(10)?,
+            busy_ms: field(12)?,
+        }))
+    })
+    .collect()
+}
+
+
+// Partitions are skipped, as their I/O is already counted in their disk. Only whole devices are listed
+// in /sys/block, when it is missing every device is kept.
+fn is_whole_device(device: &str) -> bool {
+    let sys_block = Path::new("/sys/block");
+    let whole_device = !sys_block.exists() || sys_block.join(device).exists();
+
+    whole_device && !SKIPPED_PREFIXES.iter().any(|prefix| device.starts_with(prefix))
+}
+
+
+// A counter going down means it wrapped around, so the I/O is counted as 0.
+fn device_usage(device: &str, previous: &DiskIoCounters, current: &DiskIoCounters, seconds: f64) -> DiskIoUsage {
+    let delta = |previous: u64, current: u64| current.saturating_sub(previous);
+    let rate = |delta: u64| if seconds > 0.0 { delta as f64 / seconds } else { 0.0 };
+
+    let ios = delta(previous.reads, current.reads) + delta(previous.writes, current.writes);
+    let io_ms = delta(previous.read_ms, current.read_ms) + delta(previous.write_ms, current.write_ms);
+    let busy_ms = delta(previous.busy_ms, current.busy_ms);
+
+    DiskIoUsage {
+        device: device.to_string(),
+        read_bytes: rate(delta(previous.read_sectors, current.read_sectors) * SECTOR_SIZE),
+        write_bytes: rate(delta(previous.write_sectors, current.write_sectors) * SECTOR_SIZE),
+        read_iops: rate(delta(previous.reads, current.reads)),
+        write_iops: rate(delta(previous.writes, current.writes)),
+        latency: if ios > 0 { io_ms as f64 / ios as f64 } else { 0.0 },
+        utilisation: (rate(busy_ms) / 10.0).min(100.0),
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use super::{DiskIoCounters, DiskIoTracker, DiskIoUsage, parse_diskstats};
+
+
+    #[test]
+    fn parse_diskstats_parses() {
+        let diskstats = "\
+   8       0 sda 1000 10 20000 500 400 5 8000 300 0 600 900 0 0 0 0
+   8       1 sda1 900 10 18000 450 400 5 8000 300 0 550 850
+ 259       0 broken line
+";
+
+        let counters = parse_diskstats(diskstats);
+
+        assert_eq!(counters.len(), 2);
+        assert_eq!(counters[0], ("sda".to_string(), DiskIoCounters {
+            reads: 1000,
+            read_sectors: 20000,
+            read_ms: 500,
+            writes: 400,
+            write_sectors: 8000,
+            write_ms: 300,
+            busy_ms: 600,
+        }));
+    }
+
+
+    #[test]
+    fn usage_since_computes_rates() {
+        let counters = |reads: u64, writes: u64, io_ms: u64, busy_ms: u64| DiskIoCounters {
+            reads,
+            read_sectors: reads * 8,
+            read_ms: io_ms,
+            writes,
+            write_sectors: writes * 8,
+            write_ms: 0,
+            busy_ms,
+        };
+        let mut tracker = DiskIoTracker::default();
+        let start = Instant::now();
+
+        let first = tracker.usage_since(vec![("sda".into(), counters(100, 100, 0, 0))], start);
+        let second = tracker.usage_since(vec![("sda".into(), counters(300, 200, 600, 1500))], start + Duration::from_secs(2));
+
+        assert_eq!(first, vec![]);
+        assert_eq!(second, vec![DiskIoUsage {
+            device: "sda".into(),
+            read_bytes: 409_600.0,
+            write_bytes: 204_800.0,
+            read_iops: 100.0,
+            write_iops: 50.0,
+            latency: 2.0,
+            utilisation: 75.0,
+        }]);
+    }
+}
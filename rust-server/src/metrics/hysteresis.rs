@@ -0,0 +1,167 @@
+use crate::parse_config::{Hysteresis, WarnFor};
+use super::warn::Warn;
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+
+// Tells apart the warnings of a metric about different things, ex: "disk:/var" or "ram".
+pub fn alert_key(metric: &str, subject: Option<&str>) -> String {
+    match subject {
+        Some(subject) => format!("{}:{}", metric, subject),
+        None => metric.to_string(),
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// Remembers which warnings are reported and which are waiting to be surpassed for long enough,
+// so a metric bouncing around its limit doesn't start and clear a warning on every run.
+pub struct HysteresisTracker {
+    config: Hysteresis,
+
+    // The keys of the warnings which are currently reported
+    active: HashSet<String>,
+
+    // The keys of the warnings which aren't reported yet, as (when it was first surpassed, how many runs in a row)
+    pending: HashMap<String, (Instant, u32)>,
+}
+
+
+impl HysteresisTracker {
+    pub fn new(config: &Hysteresis) -> HysteresisTracker {
+        HysteresisTracker {
+            config: config.clone(),
+            active: HashSet::new(),
+            pending: HashMap::new(),
+        }
+    }
+
+
+    // Keep the reported and waiting warnings of the tracker used before the config was reloaded.
+    pub fn keep_state(&mut self, previous: HysteresisTracker) {
+        self.active = previous.active;
+        self.pending = previous.pending;
+    }
+
+
+    // The limit a metric is checked against, its clear threshold while it is reported, otherwise its limit.
+    // A disabled (0) limit stays disabled.
+    pub fn limit(&self, metric: &str, subject: Option<&str>, limit: f64) -> f64 {
+        if limit == 0.0 || !self.active.contains(&alert_key(metric, subject)) { return limit }
+
+        match self.config.clear.iter().find(|(clear_metric, _)| clear_metric == metric) {
+            // The critical margin is a distance below the critical temperature, so a larger one is further away.
+            Some((_, clear)) if metric == "temperature_critical" => limit.max(*clear),
+            Some((_, clear)) => limit.min(*clear),
+            None => limit,
+        }
+    }
+
+
+    // Keep the warnings which were surpassed for long enough, or which are already reported. The reported and
+    // waiting warnings which weren't passed are forgotten, so they have to be surpassed for long enough again.
+    pub fn filter(&mut self, warnings: Vec<Warn>, now: Instant) -> Vec<Warn> {
+        let keys = warnings.iter().map(Warn::key).collect::<Vec<String>>();
+        self.active.retain(|key| keys.contains(key));
+        self.pending.retain(|key, _| keys.contains(key));
+
+        warnings
+        .into_iter()
+        .zip(keys)
+        .filter_map(|(warn, key)| {
+            if self.active.contains(&key) { return Some(warn) }
+
+            let warn_for = self.warn_for(warn.metric_name());
+            let (since, runs) = self.pending.entry(key.clone()).or_insert((now, 0));
+            *runs += 1;
+
+            let surpassed_long_enough = match warn_for {
+                WarnFor::Samples(samples) => *runs >= samples,
+                WarnFor::Seconds(seconds) => now.duration_since(*since) >= Duration::from_secs(seconds as u64),
+            };
+
+            if !surpassed_long_enough { return None }
+
+            self.pending.remove(&key);
+            self.active.insert(key);
+            Some(warn)
+        })
+        .collect()
+    }
+
+
+    fn warn_for(&self, metric: &str) -> WarnFor {
+        self.config.metric_for
+            .iter()
+            .find(|(for_metric, _)| for_metric == metric)
+            .map(|(_, warn_for)| *warn_for)
+            .unwrap_or(self.config.warn_for)
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::metrics::warn::{Severity, Warn};
+    use crate::parse_config::{Hysteresis, WarnFor};
+    use std::time::{Duration, Instant};
+    use super::HysteresisTracker;
+
+
+    #[test]
+    fn filter_waits_for_samples() {
+        let mut tracker = HysteresisTracker::new(&Hysteresis {
+            warn_for: WarnFor::Samples(3),
+            metric_for: vec![("disk".into(), WarnFor::Samples(1))],
+            clear: vec![],
+        });
+        let now = Instant::now();
+
+        assert_eq!(tracker.filter(vec![Warn::HighSwap(Severity::Warning, 90.0), Warn::HighDisk(Severity::Warning, "/".into(), 95.0)], now), vec![Warn::HighDisk(Severity::Warning, "/".into(), 95.0)]);
+        assert_eq!(tracker.filter(vec![Warn::HighSwap(Severity::Warning, 90.0)], now), vec![]);
+        assert_eq!(tracker.filter(vec![Warn::HighSwap(Severity::Warning, 90.0)], now), vec![Warn::HighSwap(Severity::Warning, 90.0)]);
+
+        // A single run below the limit starts the count over.
+        tracker.filter(vec![], now);
+        assert_eq!(tracker.filter(vec![Warn::HighSwap(Severity::Warning, 90.0)], now), vec![]);
+    }
+
+
+    #[test]
+    fn filter_waits_for_seconds() {
+        let mut tracker = HysteresisTracker::new(&Hysteresis {
+            warn_for: WarnFor::Seconds(30),
+            metric_for: vec![],
+            clear: vec![],
+        });
+        let start = Instant::now();
+
+        assert_eq!(tracker.filter(vec![Warn::HighSwap(Severity::Warning, 90.0)], start), vec![]);
+        assert_eq!(tracker.filter(vec![Warn::HighSwap(Severity::Warning, 90.0)], start + Duration::from_secs(20)), vec![]);
+        assert_eq!(tracker.filter(vec![Warn::HighSwap(Severity::Warning, 90.0)], start + Duration::from_secs(30)), vec![Warn::HighSwap(Severity::Warning, 90.0)]);
+    }
+
+
+    #[test]
+    fn limit_uses_clear_while_reported() {
+        let mut tracker = HysteresisTracker::new(&Hysteresis {
+            warn_for: WarnFor::default(),
+            metric_for: vec![],
+            clear: vec![("disk".into(), 70.0), ("temperature_critical".into(), 10.0)],
+        });
+
+        assert_eq!(tracker.limit("disk", Some("/"), 80.0), 80.0);
+
+        tracker.filter(vec![Warn::HighDisk(Severity::Warning, "/".into(), 85.0), Warn::NearCriticalTemperature(Severity::Warning, "cpu".into(), 96.0, 100.0)], Instant::now());
+
+        assert_eq!(tracker.limit("disk", Some("/"), 80.0), 70.0);
+        assert_eq!(tracker.limit("disk", Some("/var"), 80.0), 80.0);
+        assert_eq!(tracker.limit("disk", Some("/"), 60.0), 60.0);
+        assert_eq!(tracker.limit("disk", Some("/"), 0.0), 0.0);
+        assert_eq!(tracker.limit("temperature_critical", Some("cpu"), 5.0), 10.0);
+
+        tracker.filter(vec![], Instant::now());
+
+        assert_eq!(tracker.limit("disk", Some("/"), 80.0), 80.0);
+    }
+}
@@ -1,8 +1,12 @@
-use super::super::parse_config::{Config, ConfigMode};
+use super::super::parse_config::{Config, ConfigMode, DiskFilter};
 use sysinfo::{ProcessorExt, SystemExt};
-use super::{get_used_disk_space};
+use super::{get_core_usage, get_temperatures, get_used_disk_space, ComponentTemperature, MountUsage};
+use super::disk_io::{DiskIoTracker, DiskIoUsage};
+use super::network::{NetworkTracker, NetworkUsage};
+use super::process::{ProcessStatus, ProcessWatcher};
 
 #[derive(Debug, PartialEq)]
+#[cfg_attr(test, derive(Default))]
 pub struct IntervalMetrics {
     // Currently used ram
     pub ram: Option<u64>,
@@ -10,53 +14,102 @@ pub struct IntervalMetrics {
     // Currently used CPU
     pub cpu: Option<f32>,
 
+    // Currently used CPU of each core, in the order reported by the system
+    pub cpu_cores: Option<Vec<f32>>,
+
     // How long has the host system been running for
     pub system_uptime: Option<u64>,
 
     // CPU average for the past 1, 5 and 15 minutes.
     pub cpu_average: Option<(f64, f64, f64)>,
 
-    // Currently used disk space [In MB]
-    pub disk: Option<u64>,
+    // Currently used disk space of each watched mount point
+    pub disk: Option<Vec<MountUsage>>,
 
     // Currently used Swap in KB
     pub swap: Option<u64>,
+
+    // The state of each watched process
+    pub processes: Option<Vec<ProcessStatus>>,
+
+    // Traffic of each watched network interface since the previous run
+    pub network: Option<Vec<NetworkUsage>>,
+
+    // I/O of each block device since the previous run
+    pub disk_io: Option<Vec<DiskIoUsage>>,
+
+    // Temperature of each hardware sensor, empty when there are none
+    pub temperature: Option<Vec<ComponentTemperature>>,
+
+    // Which disks are included in the disk metric
+    pub disk_filter: DiskFilter,
+
+    // Which processes are included in the processes metric
+    pub process_watchers: Vec<ProcessWatcher>,
+
+    // The network counters of the previous run, which the traffic is computed from
+    pub network_tracker: NetworkTracker,
+
+    // The block device counters of the previous run, which the I/O is computed from
+    pub disk_io_tracker: DiskIoTracker,
 }
 
 
 
 impl IntervalMetrics {
     pub fn new(config: &Config, system: &sysinfo::System) -> IntervalMetrics {
-        if let ConfigMode::ConfigInterval {
-            ram,
-            cpu, 
-            system_uptime, 
-            cpu_average,
-            disk,
-            swap,
-        } = config.mode {
+        let interval_mode = config.modes.iter().find_map(|(mode, _)| {
+            match *mode {
+                ConfigMode::ConfigInterval { ram, cpu, cpu_cores, system_uptime, cpu_average, disk, swap, processes, network, disk_io, temperature } => {
+                    Some((ram, cpu, cpu_cores, system_uptime, cpu_average, disk, swap, processes, network, disk_io, temperature))
+                },
+                _ => None
+            }
+        });
+
+        if let Some((ram, cpu, cpu_cores, system_uptime, cpu_average, disk, swap, processes, network, disk_io, temperature)) = interval_mode {
             // Create a struct which will be filled with actual values only if the passed config has them enabled
             // -1 is used as an error (false) code.
             let mut metrics = IntervalMetrics { 
                 ram: None,
                 cpu: None,
+                cpu_cores: None,
                 system_uptime: None,
                 cpu_average: None,
                 disk: None,
                 swap: None,
+                processes: None,
+                network: None,
+                disk_io: None,
+                temperature: None,
+                disk_filter: config.disk_filter.clone(),
+                process_watchers: config.processes.iter().map(ProcessWatcher::new).collect(),
+                network_tracker: NetworkTracker::new(&config.network_filter),
+                disk_io_tracker: DiskIoTracker::default(),
             };
         
             // Check each metric, if it is enabled, set it.
-            if ram { metrics.ram = Some(system.get_used_memory() as u64) };
+            if ram { metrics.ram = Some(system.get_used_memory()) };
 
             if cpu { metrics.cpu = Some(system.get_global_processor_info().get_cpu_usage()) }
+
+            if cpu_cores { metrics.cpu_cores = Some(get_core_usage(system)) }
             
             if system_uptime { metrics.system_uptime = Some(system.get_uptime()) }
 
-            if disk { metrics.disk = Some(get_used_disk_space(system.get_disks()) as u64) }
+            if disk { metrics.disk = Some(get_used_disk_space(system.get_disks(), &metrics.disk_filter)) }
 
             if swap { metrics.swap = Some(system.get_used_swap()) }
 
+            if processes { metrics.processes = Some(get_process_status(&metrics.process_watchers, system)) }
+
+            if temperature { metrics.temperature = Some(get_temperatures(system.get_components())) }
+
+            // Only remember the counters, the first traffic and I/O are known on the next update.
+            if network { metrics.network = Some(metrics.network_tracker.usage(system)) }
+
+            if disk_io { metrics.disk_io = Some(metrics.disk_io_tracker.usage()) }
+
             if cpu_average { 
                 let avg_load = system.get_load_average();
                 metrics.cpu_average = Some((avg_load.one, avg_load.five, avg_load.fifteen));
@@ -69,20 +122,39 @@ impl IntervalMetrics {
     }
 
 
-    // Check which metric is enabled and update it. 
-    pub fn update_metrics(&mut self, system: &mut sysinfo::System) {
-        system.refresh_all();
-        
+    // Use the enabled metrics, disk filter and watched processes of a reloaded config. The network and disk I/O
+    // counters are kept, so the next update still covers the whole time since the previous one.
+    pub fn reload(&mut self, config: &Config, system: &sysinfo::System) {
+        let mut reloaded = IntervalMetrics::new(config, system);
+        reloaded.network_tracker.keep_state(std::mem::take(&mut self.network_tracker));
+        reloaded.disk_io_tracker = std::mem::take(&mut self.disk_io_tracker);
+
+        *self = reloaded;
+    }
+
+
+    // Check which metric is enabled and update it. The system needs to be refreshed beforehand.
+    pub fn update_metrics(&mut self, system: &sysinfo::System) {
         if self.ram.is_some() { self.ram = Some(system.get_used_memory()) }
 
         if self.cpu.is_some() { self.cpu = Some(system.get_global_processor_info().get_cpu_usage()) }
 
+        if self.cpu_cores.is_some() { self.cpu_cores = Some(get_core_usage(system)) }
+
         if self.system_uptime.is_some() { self.system_uptime = Some(system.get_uptime()) }
 
-        if self.disk.is_some() { self.disk = Some(get_used_disk_space(system.get_disks()) as u64 ) }
+        if self.disk.is_some() { self.disk = Some(get_used_disk_space(system.get_disks(), &self.disk_filter)) }
 
         if self.swap.is_some() { self.swap = Some(system.get_used_swap()) } 
 
+        if self.processes.is_some() { self.processes = Some(get_process_status(&self.process_watchers, system)) }
+
+        if self.temperature.is_some() { self.temperature = Some(get_temperatures(system.get_components())) }
+
+        if self.network.is_some() { self.network = Some(self.network_tracker.usage(system)) }
+
+        if self.disk_io.is_some() { self.disk_io = Some(self.disk_io_tracker.usage()) }
+
         if self.cpu_average.is_some() {
             let avg_load = system.get_load_average();
             self.cpu_average = Some((avg_load.one, avg_load.five, avg_load.fifteen));
@@ -91,6 +163,11 @@ impl IntervalMetrics {
 }
 
 
+fn get_process_status(process_watchers: &[ProcessWatcher], system: &sysinfo::System) -> Vec<ProcessStatus> {
+    process_watchers.iter().map(|watcher| watcher.status(system)).collect()
+}
+
+
 #[cfg(test)]
 mod tests {
     use super::super::super::parse_config::{Config, ConfigMode, LogType, LogCredentials};
@@ -102,30 +179,39 @@ mod tests {
     pub fn intervalmetrics_new_creates() {
         let system = sysinfo::System::new_all();
         let config = Config {
-            mode: ConfigMode::ConfigInterval {
-                ram: true,
-                cpu: true,
-                cpu_average: true,
-                system_uptime: true,
-                disk: true,
-                swap: true,
-            },
-            interval: 10,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "secret_key".to_string(),
-                channel: 123456789
-            }
+            modes: vec![
+                (ConfigMode::ConfigInterval {
+                    ram: true,
+                    cpu: true,
+                    cpu_cores: false,
+                    cpu_average: true,
+                    system_uptime: true,
+                    disk: true,
+                    swap: true,
+                    processes: false,
+                    network: false,
+                    disk_io: false,
+                    temperature: false,
+                }, 10)
+            ],
+            logs: vec![
+                (LogType::Discord, LogCredentials::DiscordLog {
+                    key: "secret_key".to_string(),
+                    channel: 123456789,
+                    critical_channel: None
+                })
+            ],
+            ..Config::default()
         };
 
         let metrics = IntervalMetrics::new(&config, &system);
 
-        assert_eq!(metrics.ram.is_some(), true);
-        assert_eq!(metrics.disk.is_some(), true);
-        assert_eq!(metrics.cpu.is_some(), true);
-        assert_eq!(metrics.cpu_average.is_some(), true);
-        assert_eq!(metrics.system_uptime.is_some(), true);
-        assert_eq!(metrics.swap.is_some(), true);
+        assert!(metrics.ram.is_some());
+        assert!(metrics.disk.is_some());
+        assert!(metrics.cpu.is_some());
+        assert!(metrics.cpu_average.is_some());
+        assert!(metrics.system_uptime.is_some());
+        assert!(metrics.swap.is_some());
     }
 
 
@@ -134,18 +220,17 @@ mod tests {
     fn intervalmetrics_new_wrong_mode() {
         let system = sysinfo::System::new_all();
         let config = Config {
-            mode: ConfigMode::ConfigWarn {
-                cpu_limit: 20,
-                ram_limit: 20,
-                disk_limit: 20,
-                swap_limit: 15,
-            },
-            interval: 10,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "secret_key".to_string(),
-                channel: 123456789
-            }
+            modes: vec![
+                (ConfigMode::warn_limits(20.0, 20.0, 20.0, 15.0), 10)
+            ],
+            logs: vec![
+                (LogType::Discord, LogCredentials::DiscordLog {
+                    key: "secret_key".to_string(),
+                    channel: 123456789,
+                    critical_channel: None
+                })
+            ],
+            ..Config::default()
         };
 
         IntervalMetrics::new(&config, &system);
@@ -156,30 +241,39 @@ mod tests {
     fn intervalmetrics_partly_enabled() {
         let system = sysinfo::System::new_all();
         let config = Config {
-            mode: ConfigMode::ConfigInterval {
-                ram: true,
-                cpu: true,
-                cpu_average: false,
-                system_uptime: false,
-                disk: true,
-                swap: false,
-            },
-            interval: 10,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "secret_key".to_string(),
-                channel: 123456789
-            }
+            modes: vec![
+                (ConfigMode::ConfigInterval {
+                    ram: true,
+                    cpu: true,
+                    cpu_cores: false,
+                    cpu_average: false,
+                    system_uptime: false,
+                    disk: true,
+                    swap: false,
+                    processes: false,
+                    network: false,
+                    disk_io: false,
+                    temperature: false,
+                }, 10)
+            ],
+            logs: vec![
+                (LogType::Discord, LogCredentials::DiscordLog {
+                    key: "secret_key".to_string(),
+                    channel: 123456789,
+                    critical_channel: None
+                })
+            ],
+            ..Config::default()
         };
 
         let metrics = IntervalMetrics::new(&config, &system);
 
-        assert_eq!(metrics.ram.is_some(), true);
-        assert_eq!(metrics.cpu.is_some(), true);
-        assert_eq!(metrics.disk.is_some(), true);
-        assert_eq!(metrics.cpu_average.is_none(), true);
-        assert_eq!(metrics.system_uptime.is_none(), true);
-        assert_eq!(metrics.swap.is_none(), true);
+        assert!(metrics.ram.is_some());
+        assert!(metrics.cpu.is_some());
+        assert!(metrics.disk.is_some());
+        assert!(metrics.cpu_average.is_none());
+        assert!(metrics.system_uptime.is_none());
+        assert!(metrics.swap.is_none());
     }
 
 
@@ -189,26 +283,36 @@ mod tests {
     fn intervalmetrics_update_metrics_updates() {
         let mut system = sysinfo::System::new_all();
         let config = Config {
-            mode: ConfigMode::ConfigInterval {
-                ram: false,
-                cpu: false,
-                cpu_average: false,
-                system_uptime: true,
-                disk: false,
-                swap: false,
-            },
-            interval: 10,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "secret_key".to_string(),
-                channel: 123456789
-            }
+            modes: vec![
+                (ConfigMode::ConfigInterval {
+                    ram: false,
+                    cpu: false,
+                    cpu_cores: false,
+                    cpu_average: false,
+                    system_uptime: true,
+                    disk: false,
+                    swap: false,
+                    processes: false,
+                    network: false,
+                    disk_io: false,
+                    temperature: false,
+                }, 10)
+            ],
+            logs: vec![
+                (LogType::Discord, LogCredentials::DiscordLog {
+                    key: "secret_key".to_string(),
+                    channel: 123456789,
+                    critical_channel: None
+                })
+            ],
+            ..Config::default()
         };
 
         let mut metrics = IntervalMetrics::new(&config, &system);
-        let old_uptime = metrics.system_uptime.clone();
+        let old_uptime = metrics.system_uptime;
         std::thread::sleep(std::time::Duration::new(1, 0));
-        metrics.update_metrics(&mut system);
+        system.refresh_all();
+        metrics.update_metrics(&system);
 
 
         assert_ne!(old_uptime, metrics.system_uptime);
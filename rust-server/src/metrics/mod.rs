@@ -1,88 +1,223 @@
-use sysinfo::{DiskExt};
+use crate::parse_config::DiskFilter;
+use sysinfo::{ComponentExt, DiskExt, ProcessorExt, System, SystemExt};
+use std::path::Path;
 
 
+pub mod alert;
+pub mod disk_io;
+pub mod hysteresis;
 pub mod interval;
+pub mod network;
+pub mod process;
 pub mod warn;
 
 
-fn get_total_disk_space<T>(disks: &[T]) -> f64 where T: DiskExt {
-    let mut total_space = 0;
+#[derive(Debug, Clone, PartialEq)]
+// The space used on a single disk, named by where it is mounted
+pub struct MountUsage {
+    pub mount_point: String,
+
+    // Used space [In MB]
+    pub used: u64,
+
+    // Total space [In MB]
+    pub total: u64,
+}
+
+
+impl MountUsage {
+    pub fn used_percent(&self) -> f64 {
+        if self.total == 0 { return 0.0 }
+
+        (self.used as f64 / self.total as f64) * 100.0
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// The temperature of a single hardware sensor [In °C]
+pub struct ComponentTemperature {
+    pub label: String,
+    pub current: f32,
+
+    // The highest temperature seen since the watcher started
+    pub max: f32,
+
+    // Not every sensor reports a critical temperature
+    pub critical: Option<f32>,
+}
 
-    disks.iter().for_each(|disk| {
-        total_space += disk.get_total_space();
-    });
 
-    (total_space / 1_000_000) as f64
+// Get the temperature of each component. Virtual machines and containers usually have no sensors,
+// in which case the list is empty. Sensors which can't be read report NaN and are skipped.
+fn get_temperatures<T>(components: &[T]) -> Vec<ComponentTemperature> where T: ComponentExt {
+    components
+    .iter()
+    .filter(|component| component.get_temperature().is_finite())
+    .map(|component| ComponentTemperature {
+        label: component.get_label().to_string(),
+        current: component.get_temperature(),
+        max: component.get_max(),
+        critical: component.get_critical().filter(|critical| critical.is_finite() && *critical > 0.0),
+    })
+    .collect()
 }
 
 
+// Get the used CPU [%] of each core.
+fn get_core_usage(system: &System) -> Vec<f32> {
+    system
+    .get_processors()
+    .iter()
+    .map(|processor| processor.get_cpu_usage())
+    .collect()
+}
+
 
-fn get_used_disk_space<T>(disks: &[T]) -> i64 where T: DiskExt {
-    let mut total_used = 0_i64;
+// Check whether the disk passes the include and exclude lists of the filter.
+fn is_watched<T>(disk: &T, filter: &DiskFilter) -> bool where T: DiskExt {
+    let mount_point = disk.get_mount_point();
+    let file_system = String::from_utf8_lossy(disk.get_file_system());
 
-    disks.iter().for_each(|disk| {
-        total_used += ((disk.get_total_space() - disk.get_available_space())  / 1_000_000) as i64;
+    let included = filter.include.is_empty() || filter.include.iter().any(|include| mount_point == Path::new(include));
+    let excluded = filter.exclude.iter().any(|exclude| {
+        if exclude.starts_with('/') {
+            mount_point.starts_with(exclude)
+        } else {
+            file_system.eq_ignore_ascii_case(exclude)
+        }
     });
 
-    total_used
+    included && !excluded
+}
+
+
+fn get_mount_usage<T>(disk: &T) -> MountUsage where T: DiskExt {
+    MountUsage {
+        mount_point: disk.get_mount_point().to_string_lossy().to_string(),
+        used: (disk.get_total_space() - disk.get_available_space()) / 1_000_000,
+        total: disk.get_total_space() / 1_000_000,
+    }
+}
+
+
+// Get the used space of every disk which passes the filter, one entry per mount point.
+fn get_used_disk_space<T>(disks: &[T], filter: &DiskFilter) -> Vec<MountUsage> where T: DiskExt {
+    disks
+    .iter()
+    .filter(|disk| is_watched(*disk, filter))
+    .map(get_mount_usage)
+    .collect()
 }
 
 
 #[cfg(test)]
 mod tests {
-    use super::{get_total_disk_space, get_used_disk_space};
-    use sysinfo::{DiskExt, DiskType};
+    use super::{get_used_disk_space, get_temperatures, ComponentTemperature, MountUsage};
+    use crate::parse_config::DiskFilter;
+    use sysinfo::{ComponentExt, DiskExt, DiskType};
     use std::ffi::OsStr;
-    use std::path::Path;
+    use std::path::{Path, PathBuf};
 
     #[derive(Debug)]
-    struct MockedDisk { available: u64, total: u64 }
+    pub struct MockedDisk { pub available: u64, pub total: u64, pub mount_point: PathBuf, pub file_system: Vec<u8> }
+
+    impl MockedDisk {
+        pub fn new(mount_point: &str, file_system: &str, available: u64, total: u64) -> MockedDisk {
+            MockedDisk { available, total, mount_point: PathBuf::from(mount_point), file_system: file_system.as_bytes().to_vec() }
+        }
+    }
 
     impl DiskExt for MockedDisk {
         fn get_type(&self) -> DiskType { DiskType::HDD }
         fn get_available_space(&self) -> u64 { self.available }
-        fn get_file_system(&self) -> &[u8] { &[2, 3, 6, 4] }
+        fn get_file_system(&self) -> &[u8] { &self.file_system }
         fn get_name(&self) -> &OsStr { OsStr::new("") }
-        fn get_mount_point(&self) -> &Path { Path::new("") }
+        fn get_mount_point(&self) -> &Path { &self.mount_point }
         fn get_total_space(&self) -> u64 { self.total }
         fn refresh(&mut self) -> bool { true }
     }
 
 
+    #[derive(Debug)]
+    pub struct MockedComponent { pub label: String, pub temperature: f32, pub max: f32, pub critical: Option<f32> }
+
+    impl MockedComponent {
+        pub fn new(label: &str, temperature: f32, max: f32, critical: Option<f32>) -> MockedComponent {
+            MockedComponent { label: label.to_string(), temperature, max, critical }
+        }
+    }
+
+    impl ComponentExt for MockedComponent {
+        fn get_temperature(&self) -> f32 { self.temperature }
+        fn get_max(&self) -> f32 { self.max }
+        fn get_critical(&self) -> Option<f32> { self.critical }
+        fn get_label(&self) -> &str { &self.label }
+        fn refresh(&mut self) {}
+    }
+
+
+    #[test]
+    fn get_used_disk_space_gets_each_mount() {
+        let disks = [
+            MockedDisk::new("/", "ext4", 1_000_000_000, 5_000_000_000), // 1 GB out of 5 GB available
+            MockedDisk::new("/var", "xfs", 450_000_000, 2_000_000_000), // 450 MB out of 2 GB available
+        ];
+
+        let used_space = get_used_disk_space(&disks, &DiskFilter::default());
+
+        assert_eq!(used_space, vec![
+            MountUsage { mount_point: "/".into(), used: 4000, total: 5000 },
+            MountUsage { mount_point: "/var".into(), used: 1550, total: 2000 },
+        ]);
+        assert!((used_space[1].used_percent() - 77.5).abs() < f64::EPSILON);
+    }
+
+
     #[test]
-    fn get_used_disk_space_gets_used() {
+    fn get_used_disk_space_skips_excluded() {
         let disks = [
-            MockedDisk {
-                available: 1_000_000_000, // 1 GB 
-                total: 5_000_000_000 // 5 GB
-            },
-
-            MockedDisk {
-                available: 450_000_000, // 450 MB
-                total: 2_000_000_000 // 2 GB
-            }
+            MockedDisk::new("/", "ext4", 1_000_000_000, 5_000_000_000),
+            MockedDisk::new("/run", "tmpfs", 0, 1_000_000_000),
+            MockedDisk::new("/var/lib/docker/overlay2/merged", "overlay", 0, 1_000_000_000),
+            MockedDisk::new("/snap/core/123", "squashfs", 0, 1_000_000_000),
         ];
 
-        let used_space = get_used_disk_space(&disks);
-        assert_eq!(used_space, ((5_000_000_000 - 1_000_000_000) + (2_000_000_000 - 450_000_000)) / 1000000);
+        let used_space = get_used_disk_space(&disks, &DiskFilter::default());
+        let mount_points = used_space.iter().map(|usage| usage.mount_point.as_str()).collect::<Vec<&str>>();
+
+        assert_eq!(mount_points, vec!["/"]);
     }
 
 
     #[test]
-    fn get_total_disk_space_gets_all_space() {
+    fn get_used_disk_space_keeps_included() {
         let disks = [
-            MockedDisk {
-                available: 1_000_000_000, // 1 GB 
-                total: 5_000_000_000 // 5 GB
-            },
-
-            MockedDisk {
-                available: 450_000_000, // 450 MB
-                total: 2_000_000_000 // 2 GB
-            }
+            MockedDisk::new("/", "ext4", 1_000_000_000, 5_000_000_000),
+            MockedDisk::new("/var", "xfs", 450_000_000, 2_000_000_000),
+            MockedDisk::new("/variable", "xfs", 450_000_000, 2_000_000_000),
+        ];
+        let filter = DiskFilter { include: vec!["/var".into()], exclude: vec![] };
+
+        let used_space = get_used_disk_space(&disks, &filter);
+        let mount_points = used_space.iter().map(|usage| usage.mount_point.as_str()).collect::<Vec<&str>>();
+
+        assert_eq!(mount_points, vec!["/var"]);
+    }
+
+
+    #[test]
+    fn get_temperatures_skips_unreadable() {
+        let components = [
+            MockedComponent::new("coretemp Package id 0", 55.0, 70.0, Some(100.0)),
+            MockedComponent::new("acpitz", 40.0, 40.0, None),
+            MockedComponent::new("nvme Composite", f32::NAN, 0.0, Some(84.8)),
         ];
 
-        let total_space = get_total_disk_space(&disks);
-        assert_eq!(total_space, 7000.0);
+        assert_eq!(get_temperatures(&components), vec![
+            ComponentTemperature { label: "coretemp Package id 0".into(), current: 55.0, max: 70.0, critical: Some(100.0) },
+            ComponentTemperature { label: "acpitz".into(), current: 40.0, max: 40.0, critical: None },
+        ]);
+        assert_eq!(get_temperatures::<MockedComponent>(&[]), vec![]);
     }
 }
@@ -0,0 +1,208 @@
+use crate::parse_config::NetworkFilter;
+use std::time::Instant;
+use sysinfo::{NetworkExt, NetworksExt, System, SystemExt};
+
+
+#[derive(Debug, Clone, PartialEq)]
+// The counters of a single network interface, as totals since the interface came up
+struct NetworkCounters {
+    rx_bytes: u64,
+    tx_bytes: u64,
+    rx_packets: u64,
+    tx_packets: u64,
+    rx_errors: u64,
+    tx_errors: u64,
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// The traffic of a single network interface between two samples
+pub struct NetworkUsage {
+    pub interface: String,
+
+    // Received and transmitted data [In bytes per second]
+    pub rx_bytes: f64,
+    pub tx_bytes: f64,
+
+    // Received and transmitted packets [Per second]
+    pub rx_packets: f64,
+    pub tx_packets: f64,
+
+    // Errors since the previous sample
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+}
+
+
+impl NetworkUsage {
+    pub fn rx_mbits(&self) -> f64 {
+        self.rx_bytes * 8.0 / 1_000_000.0
+    }
+
+
+    pub fn tx_mbits(&self) -> f64 {
+        self.tx_bytes * 8.0 / 1_000_000.0
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// Remembers the counters of each interface from the previous sample, as every mode runs on its own
+// period and the rates need to cover the whole time since that mode last ran.
+pub struct NetworkTracker {
+    filter: NetworkFilter,
+
+    // When the previous sample was taken, together with the counters of each watched interface
+    previous: Option<(Instant, Vec<(String, NetworkCounters)>)>,
+}
+
+
+impl NetworkTracker {
+    pub fn new(filter: &NetworkFilter) -> NetworkTracker {
+        NetworkTracker {
+            filter: filter.clone(),
+            previous: None,
+        }
+    }
+
+
+    // Keep the counters of the tracker used before the config was reloaded, so the next call still knows the usage.
+    pub fn keep_state(&mut self, previous: NetworkTracker) {
+        self.previous = previous.previous;
+    }
+
+
+    // Get the usage of each watched interface since the previous call, sorted by interface. The first
+    // call only remembers the counters and returns nothing. The system needs to be refreshed beforehand.
+    pub fn usage(&mut self, system: &System) -> Vec<NetworkUsage> {
+        let mut counters = system
+            .get_networks()
+            .iter()
+            .filter(|(interface, _)| is_watched(interface, &self.filter))
+            .map(|(interface, data)| (interface.clone(), NetworkCounters {
+                rx_bytes: data.get_total_received(),
+                tx_bytes: data.get_total_transmitted(),
+                rx_packets: data.get_total_packets_received(),
+                tx_packets: data.get_total_packets_transmitted(),
+                rx_errors: data.get_total_errors_on_received(),
+                tx_errors: data.get_total_errors_on_transmitted(),
+            }))
+            .collect::<Vec<(String, NetworkCounters)>>();
+
+        counters.sort_by(|(first, _), (second, _)| first.cmp(second));
+        self.usage_since(counters, Instant::now())
+    }
+
+
+    fn usage_since(&mut self, counters: Vec<(String, NetworkCounters)>, now: Instant) -> Vec<NetworkUsage> {
+        let usage = match &self.previous {
+            Some((taken_at, previous)) => {
+                let seconds = now.duration_since(*taken_at).as_secs_f64();
+
+                // Interfaces which just came up have nothing to compare against, until the next sample.
+                counters
+                .iter()
+                .filter_map(|(interface, current)| {
+                    let (_, previous) = previous.iter().find(|(previous_interface, _)| previous_interface == interface)?;
+                    Some(interface_usage(interface, previous, current, seconds))
+                })
+                .collect()
+            },
+            None => vec![],
+        };
+
+        self.previous = Some((now, counters));
+        usage
+    }
+}
+
+
+// A counter going down means the interface was reset, so its traffic is counted as 0.
+fn interface_usage(interface: &str, previous: &NetworkCounters, current: &NetworkCounters, seconds: f64) -> NetworkUsage {
+    let rate = |previous: u64, current: u64| {
+        if seconds > 0.0 { current.saturating_sub(previous) as f64 / seconds } else { 0.0 }
+    };
+
+    NetworkUsage {
+        interface: interface.to_string(),
+        rx_bytes: rate(previous.rx_bytes, current.rx_bytes),
+        tx_bytes: rate(previous.tx_bytes, current.tx_bytes),
+        rx_packets: rate(previous.rx_packets, current.rx_packets),
+        tx_packets: rate(previous.tx_packets, current.tx_packets),
+        rx_errors: current.rx_errors.saturating_sub(previous.rx_errors),
+        tx_errors: current.tx_errors.saturating_sub(previous.tx_errors),
+    }
+}
+
+
+// Check whether the interface passes the include and exclude lists of the filter.
+fn is_watched(interface: &str, filter: &NetworkFilter) -> bool {
+    let included = filter.include.is_empty() || filter.include.iter().any(|include| include == interface);
+    let excluded = filter.exclude.iter().any(|exclude| exclude == interface);
+
+    included && !excluded
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_config::NetworkFilter;
+    use std::time::{Duration, Instant};
+    use super::{NetworkCounters, NetworkTracker, NetworkUsage, is_watched};
+
+
+    fn counters(rx_bytes: u64, tx_bytes: u64, rx_errors: u64) -> NetworkCounters {
+        NetworkCounters { rx_bytes, tx_bytes, rx_packets: rx_bytes / 1000, tx_packets: tx_bytes / 1000, rx_errors, tx_errors: 0 }
+    }
+
+
+    #[test]
+    fn usage_since_computes_rates() {
+        let mut tracker = NetworkTracker::default();
+        let start = Instant::now();
+
+        let first = tracker.usage_since(vec![("eth0".into(), counters(1_000_000, 0, 1))], start);
+        let second = tracker.usage_since(
+            vec![
+                ("eth0".into(), counters(26_000_000, 2_000_000, 3)),
+                ("eth1".into(), counters(5_000_000, 0, 0)),
+            ],
+            start + Duration::from_secs(2)
+        );
+
+        assert_eq!(first, vec![]);
+        assert_eq!(second, vec![NetworkUsage {
+            interface: "eth0".into(),
+            rx_bytes: 12_500_000.0,
+            tx_bytes: 1_000_000.0,
+            rx_packets: 12_500.0,
+            tx_packets: 1000.0,
+            rx_errors: 2,
+            tx_errors: 0,
+        }]);
+        assert!((second[0].rx_mbits() - 100.0).abs() < f64::EPSILON);
+    }
+
+
+    #[test]
+    fn usage_since_handles_reset_counters() {
+        let mut tracker = NetworkTracker::default();
+        let start = Instant::now();
+
+        tracker.usage_since(vec![("eth0".into(), counters(9_000_000, 9_000_000, 5))], start);
+        let usage = tracker.usage_since(vec![("eth0".into(), counters(1000, 1000, 0))], start + Duration::from_secs(1));
+
+        assert_eq!(usage[0].rx_bytes, 0.0);
+        assert_eq!(usage[0].rx_errors, 0);
+    }
+
+
+    #[test]
+    fn is_watched_filters_interfaces() {
+        let only_eth0 = NetworkFilter { include: vec!["eth0".into()], exclude: vec![] };
+
+        assert!(is_watched("eth0", &NetworkFilter::default()));
+        assert!(!is_watched("lo", &NetworkFilter::default()));
+        assert!(!is_watched("eth1", &only_eth0));
+    }
+}
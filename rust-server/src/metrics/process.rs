@@ -0,0 +1,240 @@
+use crate::parse_config::{ProcessWatch, ProcessMatcher};
+use regex::Regex;
+use std::cmp::Reverse;
+use std::path::{Path, PathBuf};
+use sysinfo::{Pid, Process, ProcessExt, System, SystemExt};
+
+
+#[derive(Debug, Clone, PartialEq)]
+// A single process from the system's process table
+pub struct ProcessUsage {
+    pub pid: Pid,
+    pub name: String,
+
+    // The full command line, with its arguments separated by spaces
+    pub cmd: String,
+
+    // Used CPU [%], can go above 100% on systems with more than one core
+    pub cpu: f32,
+
+    // Resident memory [In KB]
+    pub memory: u64,
+}
+
+
+// Which resource the processes are sorted by.
+pub enum ProcessSort {
+    CPU,
+    Memory,
+}
+
+
+// Get every process of the system. The system needs to be refreshed beforehand.
+pub fn get_process_usage(system: &System) -> Vec<ProcessUsage> {
+    system
+    .get_processes()
+    .values()
+    .map(|process| ProcessUsage {
+        pid: process.pid(),
+        name: process.name().to_string(),
+        cmd: process.cmd().join(" "),
+        cpu: process.cpu_usage(),
+        memory: process.memory(),
+    })
+    .collect()
+}
+
+
+// Get the `count` processes which use the most of the passed resource, from the most to the least.
+pub fn top_processes(processes: &[ProcessUsage], sort: ProcessSort, count: usize) -> Vec<ProcessUsage> {
+    let mut processes = processes.to_vec();
+
+    match sort {
+        ProcessSort::CPU => processes.sort_by(|first, second| second.cpu.total_cmp(&first.cpu)),
+        ProcessSort::Memory => processes.sort_by_key(|process| Reverse(process.memory)),
+    }
+
+    processes.truncate(count);
+    processes
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// The state of a watched process. When more than one process matches (ex: the workers of a
+// web server), their usage is added up.
+pub struct ProcessStatus {
+    pub name: String,
+
+    // How many running processes matched, 0 when the process is down
+    pub running: usize,
+
+    // Used CPU [%], can go above 100% on systems with more than one core
+    pub cpu: f32,
+
+    // Resident memory [In KB]
+    pub memory: u64,
+
+    pub threads: usize,
+
+    // How long the oldest matched process has been running for [In seconds]
+    pub uptime: u64,
+}
+
+
+#[derive(Debug, Clone)]
+enum Matcher {
+    Name(String),
+    Regex(Regex),
+    PidFile(PathBuf),
+}
+
+
+// Regexes can't be compared, so they are compared by their pattern instead.
+impl PartialEq for Matcher {
+    fn eq(&self, other: &Matcher) -> bool {
+        match (self, other) {
+            (Matcher::Name(first), Matcher::Name(second)) => first == second,
+            (Matcher::Regex(first), Matcher::Regex(second)) => first.as_str() == second.as_str(),
+            (Matcher::PidFile(first), Matcher::PidFile(second)) => first == second,
+            _ => false,
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// Finds a watched process in the process table, with its regex compiled once.
+pub struct ProcessWatcher {
+    pub name: String,
+    matcher: Matcher,
+}
+
+
+impl ProcessWatcher {
+    pub fn new(watch: &ProcessWatch) -> ProcessWatcher {
+        let matcher = match &watch.matcher {
+            ProcessMatcher::Name(name) => Matcher::Name(name.clone()),
+            ProcessMatcher::Regex(pattern) => Matcher::Regex(
+                Regex::new(pattern).unwrap_or_else(|_| panic!("Couldn't parse the process.{} regex", watch.name))
+            ),
+            ProcessMatcher::PidFile(path) => Matcher::PidFile(PathBuf::from(path)),
+        };
+
+        ProcessWatcher {
+            name: watch.name.clone(),
+            matcher,
+        }
+    }
+
+
+    // Find every matching process and add up their usage. The system needs to be refreshed beforehand.
+    pub fn status(&self, system: &System) -> ProcessStatus {
+        let processes = match &self.matcher {
+            Matcher::Name(name) => system
+                .get_processes()
+                .values()
+                .filter(|process| process.name() == name)
+                .collect::<Vec<&Process>>(),
+
+            Matcher::Regex(regex) => system
+                .get_processes()
+                .values()
+                .filter(|process| regex.is_match(process.name()) || regex.is_match(&process.cmd().join(" ")))
+                .collect::<Vec<&Process>>(),
+
+            Matcher::PidFile(path) => read_pid(path)
+                .and_then(|pid| system.get_process(pid))
+                .into_iter()
+                .collect::<Vec<&Process>>(),
+        };
+
+        let now = chrono::Utc::now().timestamp() as u64;
+
+        ProcessStatus {
+            name: self.name.clone(),
+            running: processes.len(),
+            cpu: processes.iter().map(|process| process.cpu_usage()).sum(),
+            memory: processes.iter().map(|process| process.memory()).sum(),
+            threads: processes.iter().map(|process| thread_count(process)).sum(),
+            uptime: processes
+                .iter()
+                .map(|process| now.saturating_sub(process.start_time()))
+                .max()
+                .unwrap_or(0),
+        }
+    }
+}
+
+
+// Get the PID written in a PID file, None if the file is missing or doesn't contain one.
+fn read_pid(path: &Path) -> Option<Pid> {
+    std::fs::read_to_string(path).ok()?.trim().parse::<Pid>().ok()
+}
+
+
+// Threads are only listed on linux, elsewhere a process is counted as a single thread.
+#[cfg(target_os = "linux")]
+fn thread_count(process: &Process) -> usize {
+    process.tasks.len().max(1)
+}
+
+
+#[cfg(not(target_os = "linux"))]
+fn thread_count(_process: &Process) -> usize {
+    1
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parse_config::{ProcessWatch, ProcessMatcher};
+    use std::fs;
+    use sysinfo::{System, SystemExt, ProcessExt};
+    use super::{ProcessUsage, ProcessSort, ProcessWatcher, top_processes};
+
+
+    fn process(pid: i32, cpu: f32, memory: u64) -> ProcessUsage {
+        ProcessUsage { pid, name: format!("process-{}", pid), cmd: String::new(), cpu, memory }
+    }
+
+
+    #[test]
+    fn top_processes_sorts_by_resource() {
+        let processes = [process(1, 5.0, 300), process(2, 80.0, 100), process(3, 20.0, 900)];
+
+        let by_cpu = top_processes(&processes, ProcessSort::CPU, 2);
+        let by_memory = top_processes(&processes, ProcessSort::Memory, 5);
+
+        assert_eq!(by_cpu.iter().map(|process| process.pid).collect::<Vec<i32>>(), vec![2, 3]);
+        assert_eq!(by_memory.iter().map(|process| process.pid).collect::<Vec<i32>>(), vec![3, 1, 2]);
+    }
+
+
+    #[test]
+    fn status_finds_own_process() {
+        let dir = std::env::current_dir().unwrap().join("TEST_PROCESS_PIDFILE");
+        fs::create_dir(&dir).unwrap();
+        fs::write(dir.join("test.pid"), format!("{}\n", std::process::id())).unwrap();
+
+        let mut system = System::new();
+        system.refresh_processes();
+        let own_name = system.get_process(std::process::id() as i32).unwrap().name().to_string();
+
+        let watch = |matcher| ProcessWatcher::new(&ProcessWatch { name: "test".into(), matcher });
+        let by_pidfile = watch(ProcessMatcher::PidFile(dir.join("test.pid").to_str().unwrap().into())).status(&system);
+        let by_name = watch(ProcessMatcher::Name(own_name.clone())).status(&system);
+        let by_regex = watch(ProcessMatcher::Regex(format!("^{}$", regex::escape(&own_name)))).status(&system);
+        let missing = watch(ProcessMatcher::PidFile(dir.join("missing.pid").to_str().unwrap().into())).status(&system);
+
+        // Careful when editing this test to not accidently delete your files.
+        fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(by_pidfile.running, 1);
+        assert!(by_pidfile.memory > 0);
+        assert!(by_pidfile.threads >= 1);
+        assert!(by_name.running >= 1);
+        assert!(by_regex.running >= 1);
+        assert_eq!(missing.running, 0);
+        assert_eq!(missing.name, "test");
+    }
+}
@@ -1,123 +1,548 @@
-use sysinfo::{ProcessorExt, SystemExt, System};
-use super::super::parse_config::{Config, ConfigMode};
-use super::{get_total_disk_space, get_used_disk_space};
+use sysinfo::{ComponentExt, DiskExt, LoadAvg, ProcessorExt, SystemExt, System};
+use std::time::Instant;
+use super::super::parse_config::{AbsoluteLimits, Config, ConfigMode, DiskFilter};
+use super::{get_core_usage, get_mount_usage, get_temperatures, is_watched};
+use super::alert::{Alert, AlertTracker};
+use super::disk_io::{DiskIoTracker, DiskIoUsage};
+use super::hysteresis::{HysteresisTracker, alert_key};
+use super::network::{NetworkTracker, NetworkUsage};
+use super::process::{ProcessUsage, ProcessSort, ProcessWatcher, get_process_usage, top_processes};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+// How urgent a warning is, a metric is critical once it surpasses its critical level
+pub enum Severity {
+    Warning,
+    Critical,
+}
+
+
+impl Severity {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Severity::Warning => "warning",
+            Severity::Critical => "critical",
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+// The warning and the critical level of a metric, 0 disables a level
+pub struct Limit {
+    pub warning: f64,
+    pub critical: f64,
+}
+
+
+impl Limit {
+    pub fn new(warning: f64, critical: f64) -> Limit {
+        Limit { warning, critical }
+    }
 
-#[derive(Debug, PartialEq)]
+
+    // The level a metric needs to surpass to warn at all, 0 when both levels are disabled.
+    pub fn lowest(&self) -> f64 {
+        if self.warning == 0.0 {
+            self.critical
+        } else if self.critical == 0.0 {
+            self.warning
+        } else {
+            self.warning.min(self.critical)
+        }
+    }
+
+
+    pub fn severity(&self, used: f64) -> Severity {
+        if self.critical > 0.0 && used > self.critical { Severity::Critical } else { Severity::Warning }
+    }
+}
+
+
+#[derive(Debug, Clone, PartialEq)]
+// Each warning starts with its severity
 pub enum Warn {
-    HighRAM(f32),
-    HighCPU(f32),
-    HighDisk(f32),
-    HighSwap(f32),
+    // The used RAM, together with the processes using the most of it
+    HighRAM(Severity, f32, Vec<ProcessUsage>),
+    // The used CPU, together with the processes using the most of it
+    HighCPU(Severity, f32, Vec<ProcessUsage>),
+    // Each core above the limit, as (core index, used CPU)
+    HighCPUCores(Severity, Vec<(usize, f32)>),
+    // The mount point of the disk, together with its used space
+    HighDisk(Severity, String, f32),
+    HighSwap(Severity, f32),
+    // The name of a watched process which is not running
+    ProcessDown(Severity, String),
+    // The network interface, together with its received or transmitted bandwidth [In Mbit/s]
+    HighNetworkRX(Severity, String, f64),
+    HighNetworkTX(Severity, String, f64),
+    // The block device, together with how busy it was [%]
+    HighDiskIO(Severity, String, f64),
+    // The sensor, together with its temperature [In °C]
+    HighTemperature(Severity, String, f32),
+    // The sensor, together with its temperature and its critical temperature [In °C]
+    NearCriticalTemperature(Severity, String, f32, f32),
+    // The available RAM [In bytes]
+    LowFreeRAM(Severity, u64),
+    // The mount point of the disk, together with its available space [In bytes]
+    LowFreeDisk(Severity, String, u64),
+    // How many minutes the load is averaged over (1, 5 or 15), together with the load average
+    HighLoad(Severity, u32, f64),
+}
+
+
+impl Warn {
+    // The name of the metric which surpassed its limit.
+    pub fn metric_name(&self) -> &'static str {
+        match self {
+            Warn::HighRAM(_, _, _) => "ram",
+            Warn::HighCPU(_, _, _) => "cpu",
+            Warn::HighCPUCores(_, _) => "cpu_core",
+            Warn::HighDisk(_, _, _) => "disk",
+            Warn::HighSwap(_, _) => "swap",
+            Warn::ProcessDown(_, _) => "process",
+            Warn::HighNetworkRX(_, _, _) => "network_rx",
+            Warn::HighNetworkTX(_, _, _) => "network_tx",
+            Warn::HighDiskIO(_, _, _) => "disk_io",
+            Warn::HighTemperature(_, _, _) => "temperature",
+            Warn::NearCriticalTemperature(_, _, _, _) => "temperature_critical",
+            Warn::LowFreeRAM(_, _) => "ram_free",
+            Warn::LowFreeDisk(_, _, _) => "disk_free",
+            Warn::HighLoad(_, 1, _) => "load1",
+            Warn::HighLoad(_, 5, _) => "load5",
+            Warn::HighLoad(_, _, _) => "load15",
+        }
+    }
+
+
+    pub fn severity(&self) -> Severity {
+        match self {
+            Warn::HighRAM(severity, _, _)
+            | Warn::HighCPU(severity, _, _)
+            | Warn::HighCPUCores(severity, _)
+            | Warn::HighDisk(severity, _, _)
+            | Warn::HighSwap(severity, _)
+            | Warn::ProcessDown(severity, _)
+            | Warn::HighNetworkRX(severity, _, _)
+            | Warn::HighNetworkTX(severity, _, _)
+            | Warn::HighDiskIO(severity, _, _)
+            | Warn::HighTemperature(severity, _, _)
+            | Warn::NearCriticalTemperature(severity, _, _, _)
+            | Warn::LowFreeRAM(severity, _)
+            | Warn::LowFreeDisk(severity, _, _)
+            | Warn::HighLoad(severity, _, _) => *severity,
+        }
+    }
+
+
+    // What the warning is about, for metrics which can warn about more than one thing at once.
+    pub fn subject(&self) -> Option<&str> {
+        match self {
+            Warn::HighDisk(_, mount_point, _) | Warn::LowFreeDisk(_, mount_point, _) => Some(mount_point),
+            Warn::ProcessDown(_, name) => Some(name),
+            Warn::HighNetworkRX(_, interface, _) | Warn::HighNetworkTX(_, interface, _) => Some(interface),
+            Warn::HighDiskIO(_, device, _) => Some(device),
+            Warn::HighTemperature(_, label, _) | Warn::NearCriticalTemperature(_, label, _, _) => Some(label),
+            Warn::HighRAM(_, _, _) | Warn::HighCPU(_, _, _) | Warn::HighCPUCores(_, _) | Warn::HighSwap(_, _) => None,
+            Warn::LowFreeRAM(_, _) | Warn::HighLoad(_, _, _) => None,
+        }
+    }
+
+
+    // Identifies the warning between runs, ex: "disk:/var".
+    pub fn key(&self) -> String {
+        alert_key(self.metric_name(), self.subject())
+    }
 }
 
 
 pub enum MetricType {
     RAM,
     CPU,
-    Disk,
+    // The mount point of the checked disk
+    Disk(String),
     Swap,
 }
 
 
 pub struct WarnMetrics {
     // RAM Limit (%)
-    pub ram: u32,
+    pub ram: Limit,
 
     // CPU Limit (%)
-    pub cpu: u32,
+    pub cpu: Limit,
+
+    // CPU Limit (%) of each core on its own
+    pub cpu_core: Limit,
+
+    // How many cores need to be above their limit at once
+    pub cpu_core_count: u32,
+
+    // Disk usage (%) of each watched disk
+    pub disk: Limit,
+
+    // Disk usage (%) of specific mount points, checked instead of the disk's warning level
+    pub disk_mounts: Vec<(String, f64)>,
 
-    // Disk usage (%) from all disks combined
-    pub disk: u32,
+    // Which disks are checked against the disk limit
+    pub disk_filter: DiskFilter,
 
     // Swap usage (%)
-    pub swap: u32,
+    pub swap: Limit,
+
+    // How many processes to name in a RAM or CPU warning
+    pub top_processes: u32,
+
+    // Processes which are warned about once they are not running
+    pub process_watchers: Vec<ProcessWatcher>,
+
+    // Received and transmitted bandwidth (Mbit/s) of each watched network interface
+    pub network_rx: Limit,
+    pub network_tx: Limit,
+
+    // The network counters of the previous run, which the bandwidth is computed from
+    pub network_tracker: NetworkTracker,
+
+    // How busy (%) each block device was since the previous run
+    pub disk_io: Limit,
+
+    // The block device counters of the previous run, which the utilisation is computed from
+    pub disk_io_tracker: DiskIoTracker,
+
+    // Temperature (°C) of each hardware sensor, always a warning
+    pub temperature: f64,
+
+    // How close (°C) a sensor may get to its critical temperature, always critical
+    pub temperature_margin: f64,
+
+    // The free RAM, free disk space and load average limits, always a warning
+    pub absolute_limits: AbsoluteLimits,
+
+    // Which warnings are reported, to only report a limit surpassed for long enough and clear it at its clear threshold
+    pub hysteresis: HysteresisTracker,
+
+    // Which warnings are firing, to only send a warning when it starts, is reminded of and resolves
+    pub alert_tracker: AlertTracker,
+
+    // The warnings which started, were reminded of or resolved on the latest run
+    pub alerts: Vec<Alert>,
 
     // List of warnings for the different metrics if they go above limit
     pub warnings: Vec<Warn>
 }
 
 
+#[cfg(test)]
+impl Default for WarnMetrics {
+    // Every limit disabled and nothing firing, so a test only sets the fields it checks.
+    fn default() -> WarnMetrics {
+        WarnMetrics {
+            ram: Limit::default(),
+            cpu: Limit::default(),
+            cpu_core: Limit::default(),
+            cpu_core_count: 1,
+            disk: Limit::default(),
+            disk_mounts: vec![],
+            disk_filter: DiskFilter::default(),
+            swap: Limit::default(),
+            top_processes: 0,
+            process_watchers: vec![],
+            network_rx: Limit::default(),
+            network_tx: Limit::default(),
+            network_tracker: NetworkTracker::default(),
+            disk_io: Limit::default(),
+            disk_io_tracker: DiskIoTracker::default(),
+            temperature: 0.0,
+            temperature_margin: 0.0,
+            absolute_limits: AbsoluteLimits::default(),
+            hysteresis: HysteresisTracker::default(),
+            alert_tracker: AlertTracker::default(),
+            alerts: vec![],
+            warnings: vec![],
+        }
+    }
+}
+
+
 impl WarnMetrics {
     pub fn new(config: &Config) -> WarnMetrics {
-        if let ConfigMode::ConfigWarn { ram_limit, cpu_limit, disk_limit, swap_limit } = config.mode {
-            WarnMetrics {
-                ram: ram_limit,
-                cpu: cpu_limit,
-                disk: disk_limit,
-                swap: swap_limit,
-                warnings: vec![],
+        let warn_metrics = config.modes.iter().find_map(|(mode, _)| {
+            match mode {
+                ConfigMode::ConfigWarn {
+                    ram_limit,
+                    cpu_limit,
+                    cpu_core_limit,
+                    cpu_core_count,
+                    disk_limit,
+                    disk_mount_limits,
+                    swap_limit,
+                    top_processes,
+                    network_rx_limit,
+                    network_tx_limit,
+                    disk_io_limit,
+                    temperature_limit,
+                    temperature_critical_margin,
+                    absolute_limits,
+                    critical_limits,
+                    hysteresis,
+                    warn_reminder,
+                } => {
+                    let limit = |metric: &str, warning: f64| {
+                        let critical = critical_limits.iter().find(|(critical_metric, _)| critical_metric == metric);
+                        Limit::new(warning, critical.map_or(0.0, |(_, critical)| *critical))
+                    };
+
+                    Some(WarnMetrics {
+                        ram: limit("ram", *ram_limit),
+                        cpu: limit("cpu", *cpu_limit),
+                        cpu_core: limit("cpu_core", *cpu_core_limit),
+                        cpu_core_count: *cpu_core_count,
+                        disk: limit("disk", *disk_limit),
+                        disk_mounts: disk_mount_limits.clone(),
+                        disk_filter: config.disk_filter.clone(),
+                        swap: limit("swap", *swap_limit),
+                        top_processes: *top_processes,
+                        process_watchers: config.processes.iter().map(ProcessWatcher::new).collect(),
+                        network_rx: limit("network_rx", *network_rx_limit),
+                        network_tx: limit("network_tx", *network_tx_limit),
+                        network_tracker: NetworkTracker::new(&config.network_filter),
+                        disk_io: limit("disk_io", *disk_io_limit),
+                        disk_io_tracker: DiskIoTracker::default(),
+                        temperature: *temperature_limit,
+                        temperature_margin: *temperature_critical_margin,
+                        absolute_limits: absolute_limits.clone(),
+                        hysteresis: HysteresisTracker::new(hysteresis),
+                        alert_tracker: AlertTracker::new(*warn_reminder),
+                        alerts: vec![],
+                        warnings: vec![],
+                    })
+                },
+                _ => None
             }
+        });
+
+        if let Some(warn_metrics) = warn_metrics {
+            warn_metrics
         } else {
             panic!("The passed config mode does not have ConfigWarn as its mode.")
         }
     }
 
 
-    pub fn update_warns(&mut self, system: &mut System) {
+    // A single sample has no later samples to wait for, so the warn_for and clear rules are left out and every
+    // surpassed limit warns right away.
+    pub fn single_sample(config: &Config) -> WarnMetrics {
+        WarnMetrics {
+            hysteresis: HysteresisTracker::default(),
+            ..WarnMetrics::new(config)
+        }
+    }
+
+
+    // Use the limits of a reloaded config. The warnings which are reported, waiting or firing are kept, so a warning
+    // which is still above its new limit isn't sent again, and one below it resolves on the next run.
+    pub fn reload(&mut self, config: &Config) {
+        let mut reloaded = WarnMetrics::new(config);
+        reloaded.hysteresis.keep_state(std::mem::take(&mut self.hysteresis));
+        reloaded.alert_tracker.keep_state(std::mem::take(&mut self.alert_tracker));
+        reloaded.network_tracker.keep_state(std::mem::take(&mut self.network_tracker));
+        reloaded.disk_io_tracker = std::mem::take(&mut self.disk_io_tracker);
+        reloaded.alerts = std::mem::take(&mut self.alerts);
+        reloaded.warnings = std::mem::take(&mut self.warnings);
+
+        *self = reloaded;
+    }
+
+
+    // Check every enabled limit against the system. The system needs to be refreshed beforehand. A metric warns
+    // once it surpasses its lowest level, and is critical once it surpasses its critical level. A reported
+    // warning is checked against its clear threshold instead of its limit.
+    pub fn update_warns(&mut self, system: &System) {
         self.warnings.clear();
-        system.refresh_all();
 
         // Check system RAM 
-        if self.ram > 0 {
+        let ram_limit = self.hysteresis.limit("ram", None, self.ram.lowest());
+        if ram_limit > 0.0 {
             let limit = above_limit(
-    self.ram as f64,
+    ram_limit,
     system.get_total_memory() as f64,
      system.get_used_memory() as f64, 
-                MetricType::RAM
+                MetricType::RAM,
+                &self.ram
             );
 
             if let Ok(warn) = limit { self.warnings.push(warn) }
         } 
 
         // Check system CPU
-        if self.cpu > 0 {
+        let cpu_limit = self.hysteresis.limit("cpu", None, self.cpu.lowest());
+        if cpu_limit > 0.0 {
             let limit = above_limit(
-    self.cpu as f64,
-    100 as f64,
+    cpu_limit,
+    100.0,
      system.get_global_processor_info().get_cpu_usage() as f64,
-                MetricType::CPU
+                MetricType::CPU,
+                &self.cpu
             );
 
             if let Ok(warn) = limit { self.warnings.push(warn) }
         }
 
-        // Check system space
-        if self.disk > 0 {
-            let limit = above_limit(
-    self.disk as f64, 
-    get_total_disk_space(system.get_disks()), 
-     get_used_disk_space(system.get_disks()) as f64, 
-                MetricType::Disk
-            );
-
-            if let Ok(warn) = limit { self.warnings.push(warn) }
+        // Check each core of the system
+        let cpu_core_limit = self.hysteresis.limit("cpu_core", None, self.cpu_core.lowest());
+        if cpu_core_limit > 0.0 {
+            let core_warn = cores_above_limit(cpu_core_limit as f32, self.cpu_core.critical as f32, self.cpu_core_count, &get_core_usage(system));
+            if let Some(warn) = core_warn { self.warnings.push(warn) }
         }
 
-        if self.swap > 0 {
+        // Check system space
+        let disk_warns = self.disk_warns(system.get_disks());
+        self.warnings.extend(disk_warns);
+
+        let swap_limit = self.hysteresis.limit("swap", None, self.swap.lowest());
+        if swap_limit > 0.0 {
             let limit = above_limit(
-    self.swap as f64, 
+    swap_limit, 
     system.get_total_swap() as f64, 
      system.get_used_swap() as f64, 
-                MetricType::Swap
+                MetricType::Swap,
+                &self.swap
             );
 
             if let Ok(warn) = limit { self.warnings.push(warn) }
         }
+
+        // Check the limits in the metric's own unit
+        let available_ram = system.get_available_memory() * 1000;
+        if self.absolute_limits.ram_free_below > 0 && available_ram < self.absolute_limits.ram_free_below {
+            self.warnings.push(Warn::LowFreeRAM(Severity::Warning, available_ram));
+        }
+
+        let disk_free_warns = self.disk_free_warns(system.get_disks());
+        self.warnings.extend(disk_free_warns);
+
+        let load_warns = load_warns(&self.absolute_limits, &system.get_load_average(), &self.hysteresis);
+        self.warnings.extend(load_warns);
+
+        // Check the bandwidth of each network interface. Nothing is known on the first run, as the
+        // bandwidth is computed from the counters of the previous run.
+        if self.network_rx.lowest() > 0.0 || self.network_tx.lowest() > 0.0 {
+            let network_usage = self.network_tracker.usage(system);
+            let network_warns = network_warns(self.network_rx, self.network_tx, &network_usage, &self.hysteresis);
+            self.warnings.extend(network_warns);
+        }
+
+        // Check how busy each block device was, the same way as the bandwidth.
+        if self.disk_io.lowest() > 0.0 {
+            let disk_io_warns = disk_io_warns(self.disk_io, &self.disk_io_tracker.usage(), &self.hysteresis);
+            self.warnings.extend(disk_io_warns);
+        }
+
+        // Check each hardware sensor, nothing is checked when there are none
+        let temperature_warns = self.temperature_warns(system.get_components());
+        self.warnings.extend(temperature_warns);
+
+        // Check each watched process is still running
+        for watcher in self.process_watchers.iter() {
+            if watcher.status(system).running == 0 { self.warnings.push(Warn::ProcessDown(Severity::Critical, watcher.name.clone())) }
+        }
+
+        let warnings = std::mem::take(&mut self.warnings);
+        self.warnings = self.hysteresis.filter(warnings, Instant::now());
+
+        if self.top_processes > 0 { self.attach_top_processes(system) }
+
+        self.alerts = self.alert_tracker.update(&self.warnings, Instant::now());
+    }
+
+
+    // Name the processes using the most of the resource behind each RAM and CPU warning.
+    fn attach_top_processes(&mut self, system: &System) {
+        let count = self.top_processes as usize;
+        let needed = self.warnings.iter().any(|warn| matches!(warn, Warn::HighRAM(_, _, _) | Warn::HighCPU(_, _, _)));
+        if !needed { return }
+
+        let processes = get_process_usage(system);
+
+        for warn in self.warnings.iter_mut() {
+            match warn {
+                Warn::HighRAM(_, _, top) => *top = top_processes(&processes, ProcessSort::Memory, count),
+                Warn::HighCPU(_, _, top) => *top = top_processes(&processes, ProcessSort::CPU, count),
+                _ => {}
+            }
+        }
+    }
+
+
+    // Check each disk on its own, against the limit of its mount point if it has one,
+    // otherwise against the disk limit if the disk is watched. Every disk shares the critical level.
+    fn disk_warns<T>(&self, disks: &[T]) -> Vec<Warn> where T: DiskExt {
+        disks.iter().filter_map(|disk| {
+            let usage = get_mount_usage(disk);
+            let limit = match self.disk_mounts.iter().find(|(mount_point, _)| *mount_point == usage.mount_point) {
+                Some((_, limit)) => Limit::new(*limit, self.disk.critical),
+                None if is_watched(disk, &self.disk_filter) => self.disk,
+                None => Limit::default(),
+            };
+
+            let lowest = self.hysteresis.limit("disk", Some(&usage.mount_point), limit.lowest());
+            if lowest == 0.0 { return None }
+
+            above_limit(lowest, usage.total as f64, usage.used as f64, MetricType::Disk(usage.mount_point), &limit).ok()
+        })
+        .collect()
+    }
+
+
+    // Check the available space of each watched disk against the disk_free_below limit.
+    fn disk_free_warns<T>(&self, disks: &[T]) -> Vec<Warn> where T: DiskExt {
+        if self.absolute_limits.disk_free_below == 0 { return vec![] }
+
+        disks
+        .iter()
+        .filter(|disk| is_watched(*disk, &self.disk_filter) && disk.get_available_space() < self.absolute_limits.disk_free_below)
+        .map(|disk| Warn::LowFreeDisk(Severity::Warning, get_mount_usage(disk).mount_point, disk.get_available_space()))
+        .collect()
+    }
+
+
+    // Check each sensor against the temperature limit, then against its own critical temperature.
+    // A sensor only warns once, and sensors without a critical temperature are only checked against the limit.
+    // Getting close to the critical temperature is always critical.
+    fn temperature_warns<T>(&self, components: &[T]) -> Vec<Warn> where T: ComponentExt {
+        if self.temperature == 0.0 && self.temperature_margin == 0.0 { return vec![] }
+
+        get_temperatures(components)
+        .into_iter()
+        .filter_map(|sensor| {
+            let margin = self.hysteresis.limit("temperature_critical", Some(&sensor.label), self.temperature_margin);
+            let limit = self.hysteresis.limit("temperature", Some(&sensor.label), self.temperature);
+            let near_critical = sensor.critical.filter(|critical| margin > 0.0 && sensor.current >= critical - margin as f32);
+
+            if let Some(critical) = near_critical {
+                Some(Warn::NearCriticalTemperature(Severity::Critical, sensor.label, sensor.current, critical))
+            } else if limit > 0.0 && sensor.current >= limit as f32 {
+                Some(Warn::HighTemperature(Severity::Warning, sensor.label, sensor.current))
+            } else {
+                None
+            }
+        })
+        .collect()
     }
 }
 
 
 // Check to see if the current metric usage is above the passed percentage.
-// If it is, return the % of the metric that is used to the warn vector.
-fn above_limit(metric_limit: f64, total_metric: f64, used_metric: f64, metric_type: MetricType) -> Result<Warn, bool> {
+// If it is, return the % of the metric that is used to the warn vector, with its severity from the metric's levels.
+fn above_limit(metric_limit: f64, total_metric: f64, used_metric: f64, metric_type: MetricType, levels: &Limit) -> Result<Warn, bool> {
     let used_percentage = (used_metric / total_metric) * 100.0;
+    let severity = levels.severity(used_percentage);
     
     if used_percentage > metric_limit {
         match metric_type {
-            MetricType::RAM => Ok(Warn::HighRAM(used_percentage as f32)),
-            MetricType::CPU => Ok(Warn::HighCPU(used_percentage as f32)),
-            MetricType::Disk => Ok(Warn::HighDisk(used_percentage as f32)),
-            MetricType::Swap => Ok(Warn::HighSwap(used_percentage as f32)),
+            MetricType::RAM => Ok(Warn::HighRAM(severity, used_percentage as f32, vec![])),
+            MetricType::CPU => Ok(Warn::HighCPU(severity, used_percentage as f32, vec![])),
+            MetricType::Disk(mount_point) => Ok(Warn::HighDisk(severity, mount_point, used_percentage as f32)),
+            MetricType::Swap => Ok(Warn::HighSwap(severity, used_percentage as f32)),
         }
     } else {
         Err(false)
@@ -125,35 +550,111 @@ fn above_limit(metric_limit: f64, total_metric: f64, used_metric: f64, metric_ty
 }
 
 
+// Check to see if at least `count` cores are above the passed percentage.
+// If they are, return every core which is above it. Critical once `count` cores are above the critical level.
+fn cores_above_limit(core_limit: f32, core_critical: f32, count: u32, core_usage: &[f32]) -> Option<Warn> {
+    let above = core_usage
+        .iter()
+        .enumerate()
+        .filter(|(_, used)| **used > core_limit)
+        .map(|(core, used)| (core, *used))
+        .collect::<Vec<(usize, f32)>>();
+
+    let critical_cores = above.iter().filter(|(_, used)| core_critical > 0.0 && *used > core_critical).count();
+    let severity = if core_critical > 0.0 && critical_cores >= count as usize { Severity::Critical } else { Severity::Warning };
+
+    if !above.is_empty() && above.len() >= count as usize {
+        Some(Warn::HighCPUCores(severity, above))
+    } else {
+        None
+    }
+}
+
+
+// Check the received and transmitted bandwidth of each interface against their limits, 0 disables a limit.
+fn network_warns(rx_limit: Limit, tx_limit: Limit, network_usage: &[NetworkUsage], hysteresis: &HysteresisTracker) -> Vec<Warn> {
+    let mut warnings = Vec::new();
+
+    for usage in network_usage {
+        let rx_lowest = hysteresis.limit("network_rx", Some(&usage.interface), rx_limit.lowest());
+        let tx_lowest = hysteresis.limit("network_tx", Some(&usage.interface), tx_limit.lowest());
+
+        if rx_lowest > 0.0 && usage.rx_mbits() > rx_lowest {
+            warnings.push(Warn::HighNetworkRX(rx_limit.severity(usage.rx_mbits()), usage.interface.clone(), usage.rx_mbits()));
+        }
+
+        if tx_lowest > 0.0 && usage.tx_mbits() > tx_lowest {
+            warnings.push(Warn::HighNetworkTX(tx_limit.severity(usage.tx_mbits()), usage.interface.clone(), usage.tx_mbits()));
+        }
+    }
+
+    warnings
+}
+
+
+// Check the utilisation of each block device against the limit.
+fn disk_io_warns(limit: Limit, disk_io_usage: &[DiskIoUsage], hysteresis: &HysteresisTracker) -> Vec<Warn> {
+    disk_io_usage
+    .iter()
+    .filter(|usage| usage.utilisation > hysteresis.limit("disk_io", Some(&usage.device), limit.lowest()))
+    .map(|usage| Warn::HighDiskIO(limit.severity(usage.utilisation), usage.device.clone(), usage.utilisation))
+    .collect()
+}
+
+
+// Check the 1, 5 and 15 minute load averages against their limits, 0 disables a limit.
+fn load_warns(limits: &AbsoluteLimits, load: &LoadAvg, hysteresis: &HysteresisTracker) -> Vec<Warn> {
+    let averages = [
+        (1, "load1", limits.load1_above, load.one),
+        (5, "load5", limits.load5_above, load.five),
+        (15, "load15", limits.load15_above, load.fifteen),
+    ];
+
+    averages
+    .iter()
+    .filter(|(_, metric, limit, load)| {
+        let limit = hysteresis.limit(metric, None, *limit);
+        limit > 0.0 && *load > limit
+    })
+    .map(|(minutes, _, _, load)| Warn::HighLoad(Severity::Warning, *minutes, *load))
+    .collect()
+}
+
+
 #[cfg(test)]
 mod tests {
-    use super::super::super::parse_config::{Config, ConfigMode, LogType, LogCredentials};
-    use super::{WarnMetrics, above_limit, MetricType, Warn};
+    use super::super::super::parse_config::{Config, ConfigMode, LogType, LogCredentials, ProcessWatch, ProcessMatcher, AbsoluteLimits};
+    use super::super::tests::{MockedComponent, MockedDisk};
+    use super::super::disk_io::DiskIoUsage;
+    use super::super::hysteresis::HysteresisTracker;
+    use super::super::network::NetworkUsage;
+    use super::super::process::ProcessWatcher;
+    use super::{WarnMetrics, above_limit, cores_above_limit, network_warns, disk_io_warns, load_warns, MetricType, Limit, Severity, Warn};
+    use sysinfo::{LoadAvg, ProcessExt, System, SystemExt};
 
 
     #[test]
     pub fn metricwarns_creates() {
         let config = Config {
-            mode: ConfigMode::ConfigWarn {
-                ram_limit: 40,
-                cpu_limit: 45,
-                disk_limit: 50,
-                swap_limit: 34,
-            },
-            interval: 10,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "secret_key".to_string(),
-                channel: 123456789
-            }
+            modes: vec![
+                (ConfigMode::warn_limits(40.0, 45.0, 50.0, 34.0), 10)
+            ],
+            logs: vec![
+                (LogType::Discord, LogCredentials::DiscordLog {
+                    key: "secret_key".to_string(),
+                    channel: 123456789,
+                    critical_channel: None
+                })
+            ],
+            ..Config::default()
         };
 
         let metric_warns = WarnMetrics::new(&config);
 
-        assert_eq!(metric_warns.cpu, 45);
-        assert_eq!(metric_warns.ram, 40);
-        assert_eq!(metric_warns.disk, 50);
-        assert_eq!(metric_warns.swap, 34);
+        assert_eq!(metric_warns.cpu, Limit::new(45.0, 0.0));
+        assert_eq!(metric_warns.ram, Limit::new(40.0, 0.0));
+        assert_eq!(metric_warns.disk, Limit::new(50.0, 0.0));
+        assert_eq!(metric_warns.swap, Limit::new(34.0, 0.0));
         assert_eq!(metric_warns.warnings.len(), 0);
     }
 
@@ -162,20 +663,29 @@ mod tests {
     #[should_panic = "The passed config mode does not have ConfigWarn as its mode."]
     pub fn metricwarns_wrong_mode() {
         let config = Config {
-            mode: ConfigMode::ConfigInterval {
-                ram: true,
-                cpu: true,
-                cpu_average: true,
-                system_uptime: true,
-                disk: true,
-                swap: true,
-            },
-            interval: 10,
-            log_type: LogType::Discord,
-            log_credentials: LogCredentials::DiscordLog {
-                key: "secret_key".to_string(),
-                channel: 123456789
-            }
+            modes: vec![
+                (ConfigMode::ConfigInterval {
+                    ram: true,
+                    cpu: true,
+                    cpu_cores: false,
+                    cpu_average: true,
+                    system_uptime: true,
+                    disk: true,
+                    swap: true,
+                    processes: false,
+                    network: false,
+                    disk_io: false,
+                    temperature: false,
+                }, 10)
+            ],
+            logs: vec![
+                (LogType::Discord, LogCredentials::DiscordLog {
+                    key: "secret_key".to_string(),
+                    channel: 123456789,
+                    critical_channel: None
+                })
+            ],
+            ..Config::default()
         };
 
         WarnMetrics::new(&config);
@@ -188,11 +698,12 @@ mod tests {
 20.0,
 100.0,
  15.0,
-            MetricType::RAM
+            MetricType::RAM,
+            &Limit::new(20.0, 0.0)
         );
 
         if let Err(above_limit) = limit {
-            assert_eq!(above_limit, false)
+            assert!(!above_limit)
         } else {
             panic!("It should have been an error")
         }
@@ -205,16 +716,171 @@ mod tests {
 20.0,
 100.0,
  50.0,
-            MetricType::RAM
+            MetricType::RAM,
+            &Limit::new(20.0, 0.0)
         );
 
         if let Ok(above_limit) = limit {
-            assert_eq!(above_limit, Warn::HighRAM(50.0))
+            assert_eq!(above_limit, Warn::HighRAM(Severity::Warning, 50.0, vec![]))
         } else {
             panic!("It should have been an error")
         }
     }
-}
 
 
+    #[test]
+    pub fn above_limit_above_critical() {
+        let limit = above_limit(20.0, 100.0, 95.0, MetricType::Swap, &Limit::new(20.0, 90.0));
+
+        assert_eq!(limit, Ok(Warn::HighSwap(Severity::Critical, 95.0)));
+    }
 
+
+    #[test]
+    pub fn disk_warns_checks_each_mount() {
+        let mut metrics = WarnMetrics {
+            disk: Limit::new(70.0, 0.0),
+            disk_mounts: vec![("/var".into(), 90.0), ("/boot".into(), 0.0)],
+            ..WarnMetrics::default()
+        };
+        let disks = [
+            MockedDisk::new("/", "ext4", 1_000_000_000, 5_000_000_000), // 80% used
+            MockedDisk::new("/var", "xfs", 400_000_000, 2_000_000_000), // 80% used, below its own limit
+            MockedDisk::new("/boot", "ext4", 0, 1_000_000_000), // Full, but disabled
+            MockedDisk::new("/run", "tmpfs", 0, 1_000_000_000), // Full, but excluded
+        ];
+
+        assert_eq!(metrics.disk_warns(&disks), vec![Warn::HighDisk(Severity::Warning, "/".into(), 80.0)]);
+
+        metrics.disk = Limit::new(0.0, 0.0);
+        metrics.disk_mounts = vec![("/var".into(), 75.0)];
+
+        assert_eq!(metrics.disk_warns(&disks), vec![Warn::HighDisk(Severity::Warning, "/var".into(), 80.0)]);
+    }
+
+
+    #[test]
+    pub fn disk_free_warns_checks_watched_disks() {
+        let metrics = WarnMetrics {
+            absolute_limits: AbsoluteLimits { disk_free_below: 10_000_000_000, ..AbsoluteLimits::default() },
+            ..WarnMetrics::default()
+        };
+        let disks = [
+            MockedDisk::new("/", "ext4", 5_000_000_000, 100_000_000_000),
+            MockedDisk::new("/data", "xfs", 50_000_000_000, 100_000_000_000),
+            MockedDisk::new("/run", "tmpfs", 0, 1_000_000_000), // Full, but excluded
+        ];
+
+        assert_eq!(metrics.disk_free_warns(&disks), vec![Warn::LowFreeDisk(Severity::Warning, "/".into(), 5_000_000_000)]);
+    }
+
+
+    #[test]
+    pub fn load_warns_checks_each_average() {
+        let limits = AbsoluteLimits { load1_above: 8.0, load15_above: 4.0, ..AbsoluteLimits::default() };
+        let load = LoadAvg { one: 9.5, five: 20.0, fifteen: 3.5 };
+
+        assert_eq!(load_warns(&limits, &load, &HysteresisTracker::default()), vec![Warn::HighLoad(Severity::Warning, 1, 9.5)]);
+    }
+
+
+    #[test]
+    pub fn cores_above_limit_counts_cores() {
+        let core_usage = [10.0, 95.0, 30.0, 99.0];
+
+        assert_eq!(cores_above_limit(90.0, 0.0, 1, &core_usage), Some(Warn::HighCPUCores(Severity::Warning, vec![(1, 95.0), (3, 99.0)])));
+        assert_eq!(cores_above_limit(90.0, 0.0, 2, &core_usage), Some(Warn::HighCPUCores(Severity::Warning, vec![(1, 95.0), (3, 99.0)])));
+        assert_eq!(cores_above_limit(90.0, 0.0, 3, &core_usage), None);
+        assert_eq!(cores_above_limit(99.0, 0.0, 1, &core_usage), None);
+        assert_eq!(cores_above_limit(90.0, 96.0, 1, &core_usage), Some(Warn::HighCPUCores(Severity::Critical, vec![(1, 95.0), (3, 99.0)])));
+        assert_eq!(cores_above_limit(90.0, 96.0, 2, &core_usage), Some(Warn::HighCPUCores(Severity::Warning, vec![(1, 95.0), (3, 99.0)])));
+    }
+
+
+    #[test]
+    pub fn update_warns_finds_down_processes() {
+        let mut system = System::new();
+        system.refresh_processes();
+        let own_name = system.get_process(std::process::id() as i32).unwrap().name().to_string();
+
+        let mut metrics = WarnMetrics {
+            process_watchers: vec![
+                ProcessWatcher::new(&ProcessWatch { name: "own".into(), matcher: ProcessMatcher::Name(own_name) }),
+                ProcessWatcher::new(&ProcessWatch { name: "missing".into(), matcher: ProcessMatcher::PidFile("TEST_MISSING.pid".into()) }),
+            ],
+            ..WarnMetrics::default()
+        };
+
+        metrics.update_warns(&system);
+
+        assert_eq!(metrics.warnings, vec![Warn::ProcessDown(Severity::Critical, "missing".into())]);
+        assert_eq!(metrics.alerts.len(), 1);
+    }
+
+
+    #[test]
+    pub fn network_warns_checks_each_interface() {
+        let usage = |interface: &str, rx_mbits: f64, tx_mbits: f64| NetworkUsage {
+            interface: interface.into(),
+            rx_bytes: rx_mbits * 125_000.0,
+            tx_bytes: tx_mbits * 125_000.0,
+            rx_packets: 0.0,
+            tx_packets: 0.0,
+            rx_errors: 0,
+            tx_errors: 0,
+        };
+        let network_usage = [usage("eth0", 150.0, 20.0), usage("eth1", 10.0, 900.0)];
+
+        assert_eq!(network_warns(Limit::new(100.0, 0.0), Limit::new(500.0, 0.0), &network_usage, &HysteresisTracker::default()), vec![
+            Warn::HighNetworkRX(Severity::Warning, "eth0".into(), 150.0),
+            Warn::HighNetworkTX(Severity::Warning, "eth1".into(), 900.0),
+        ]);
+        assert_eq!(network_warns(Limit::new(0.0, 0.0), Limit::new(1000.0, 0.0), &network_usage, &HysteresisTracker::default()), vec![]);
+    }
+
+
+    #[test]
+    pub fn disk_io_warns_checks_each_device() {
+        let usage = |device: &str, utilisation: f64| DiskIoUsage {
+            device: device.into(),
+            read_bytes: 0.0,
+            write_bytes: 0.0,
+            read_iops: 0.0,
+            write_iops: 0.0,
+            latency: 0.0,
+            utilisation,
+        };
+
+        assert_eq!(
+            disk_io_warns(Limit::new(90.0, 0.0), &[usage("sda", 95.0), usage("nvme0n1", 90.0)], &HysteresisTracker::default()),
+            vec![Warn::HighDiskIO(Severity::Warning, "sda".into(), 95.0)]
+        );
+    }
+
+
+    #[test]
+    pub fn temperature_warns_checks_each_sensor() {
+        let mut metrics = WarnMetrics {
+            temperature: 80.0,
+            temperature_margin: 5.0,
+            ..WarnMetrics::default()
+        };
+        let components = [
+            MockedComponent::new("cpu", 85.0, 90.0, Some(100.0)), // Above the limit
+            MockedComponent::new("nvme", 81.0, 81.0, Some(84.8)), // Within 5°C of critical
+            MockedComponent::new("gpu", 97.0, 97.0, None), // Above the limit, no critical temperature
+            MockedComponent::new("acpitz", 40.0, 40.0, None),
+        ];
+
+        assert_eq!(metrics.temperature_warns(&components), vec![
+            Warn::HighTemperature(Severity::Warning, "cpu".into(), 85.0),
+            Warn::NearCriticalTemperature(Severity::Critical, "nvme".into(), 81.0, 84.8),
+            Warn::HighTemperature(Severity::Warning, "gpu".into(), 97.0),
+        ]);
+        assert_eq!(metrics.temperature_warns::<MockedComponent>(&[]), vec![]);
+
+        metrics.temperature = 0.0;
+
+        assert_eq!(metrics.temperature_warns(&components), vec![Warn::NearCriticalTemperature(Severity::Critical, "nvme".into(), 81.0, 84.8)]);
+    }
+}
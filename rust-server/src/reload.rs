@@ -0,0 +1,186 @@
+use crate::{config_file, parse_config};
+use crate::parse_config::Config;
+use std::time::{Duration, SystemTime};
+
+
+type Vars = Vec<(String, String)>;
+
+
+// How often the .env and config files are checked for changes.
+pub const POLL_PERIOD: Duration = Duration::from_secs(5);
+
+
+#[derive(Debug, Clone, PartialEq, Default)]
+// The files the config is read from, read again every time the config is reloaded
+pub struct ConfigSources {
+    pub env_file: Option<String>,
+    pub config_file: Option<String>,
+
+    // When each file was modified and its size, as of the last time it was read
+    modified: Vec<Option<(SystemTime, u64)>>,
+}
+
+
+impl ConfigSources {
+    pub fn new(env_file: Option<String>, config_file: Option<String>) -> ConfigSources {
+        ConfigSources {
+            env_file,
+            config_file,
+            modified: vec![],
+        }
+    }
+
+
+    // Read the files into the variables the config is parsed from.
+    pub fn load(&mut self) -> Result<(), String> {
+        self.modified = self.modified_times();
+        let (env_vars, file_vars) = self.read()?;
+
+        parse_config::set_env_file_vars(env_vars);
+        parse_config::set_file_vars(file_vars);
+        Ok(())
+    }
+
+
+    // Whether a file was modified, created or removed since it was last read.
+    pub fn changed(&self) -> bool {
+        self.modified_times() != self.modified
+    }
+
+
+    // Read the files again and parse the config. If a file can't be read or the config has a problem, it is printed
+    // and the previous variables are used again, so the running config stays as it is.
+    pub fn reload(&mut self) -> Option<Config> {
+        // A broken file is only reported once, not on every check for changes.
+        self.modified = self.modified_times();
+
+        let (env_vars, file_vars) = match self.read() {
+            Ok(vars) => vars,
+            Err(err) => {
+                eprintln!("{}, keeping the previous config", err);
+                return None;
+            },
+        };
+
+        let previous_env_vars = parse_config::set_env_file_vars(env_vars);
+        let previous_file_vars = parse_config::set_file_vars(file_vars);
+
+        match parse_config::create_config() {
+            Ok(config) => Some(config),
+            Err(errors) => {
+                eprintln!("{}\nKeeping the previous config", parse_config::error_report(&errors));
+                parse_config::set_env_file_vars(previous_env_vars);
+                parse_config::set_file_vars(previous_file_vars);
+                None
+            },
+        }
+    }
+
+
+    // The variables of the .env file and of the config file.
+    fn read(&self) -> Result<(Vars, Vars), String> {
+        let env_vars = match &self.env_file {
+            Some(path) => config_file::read_env_file(path)?,
+            None => vec![],
+        };
+
+        let file_vars = match &self.config_file {
+            Some(path) => config_file::read_config_file(path)?,
+            None => vec![],
+        };
+
+        Ok((env_vars, file_vars))
+    }
+
+
+    fn modified_times(&self) -> Vec<Option<(SystemTime, u64)>> {
+        [&self.env_file, &self.config_file]
+            .iter()
+            .map(|path| {
+                let metadata = std::fs::metadata(path.as_ref()?).ok()?;
+                Some((metadata.modified().ok()?, metadata.len()))
+            })
+            .collect()
+    }
+}
+
+
+// Resolves every time the process is sent a SIGHUP (ex: kill -HUP <pid>). Never resolves on systems without signals.
+pub struct Hangup {
+    #[cfg(unix)]
+    signal: tokio::signal::unix::Signal,
+}
+
+
+impl Hangup {
+    // Needs to be called inside the tokio runtime.
+    pub fn listen() -> Hangup {
+        Hangup {
+            #[cfg(unix)]
+            signal: tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()).expect("Couldn't listen for SIGHUP"),
+        }
+    }
+
+
+    pub async fn recv(&mut self) {
+        #[cfg(unix)]
+        self.signal.recv().await;
+
+        #[cfg(not(unix))]
+        std::future::pending::<()>().await;
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use std::env::{set_var, remove_var};
+    use std::fs;
+    use crate::parse_config::{self, ConfigMode};
+    use crate::test_env::lock_env;
+    use super::ConfigSources;
+
+
+    #[test]
+    fn reload_keeps_previous_config_on_problems() {
+        let _env = lock_env();
+        // Careful when editing this test to not accidently delete your files.
+        let dir = std::env::current_dir().unwrap().join("TEST_RELOAD");
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(".env");
+        let env = "mode=warn\ninterval=10\ncpu_limit=20\ndisk_limit=20\nswap_limit=20\ntype=prometheus\n";
+
+        for var in ["mode", "interval", "ram_limit", "cpu_limit", "disk_limit", "swap_limit", "type"].iter() {
+            remove_var(var);
+        }
+        set_var("prometheus_address", "127.0.0.1:0");
+
+        let mut sources = ConfigSources::new(Some(path.to_str().unwrap().to_string()), None);
+        fs::write(&path, format!("{}ram_limit=80\n", env)).unwrap();
+        sources.load().unwrap();
+        let unchanged = sources.changed();
+
+        fs::write(&path, format!("{}ram_limit=90\n# changed\n", env)).unwrap();
+        let changed = sources.changed();
+        let reloaded = sources.reload();
+
+        fs::write(&path, format!("{}ram_limit=120\n", env)).unwrap();
+        let broken = sources.reload();
+        let kept = parse_config::create_config();
+
+        parse_config::set_env_file_vars(vec![]);
+        remove_var("prometheus_address");
+        fs::remove_dir_all(&dir).unwrap();
+
+        let ram_limit = |config: parse_config::Config| match config.modes[0].0 {
+            ConfigMode::ConfigWarn { ram_limit, .. } => ram_limit,
+            _ => panic!("It should have been the warn mode"),
+        };
+
+        assert!(!unchanged);
+        assert!(changed);
+        assert_eq!(reloaded.map(ram_limit), Some(90.0));
+        assert!(broken.is_none());
+        assert_eq!(kept.map(ram_limit), Ok(90.0));
+    }
+}
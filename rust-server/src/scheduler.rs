@@ -0,0 +1,357 @@
+use crate::logging::{self, Sink, Sample};
+use crate::metrics::{interval::IntervalMetrics, warn::WarnMetrics};
+use crate::parse_config::{Config, ConfigMode, LogType, LogCredentials};
+use crate::reload::{ConfigSources, Hangup, POLL_PERIOD};
+use sysinfo::{System, SystemExt};
+use std::time::Duration;
+use tokio::time::Instant;
+
+
+// A mode which is being watched, together with the metrics it keeps between runs.
+#[allow(clippy::large_enum_variant)]
+enum Watcher {
+    Interval(IntervalMetrics),
+    Warn(WarnMetrics),
+}
+
+
+impl Watcher {
+    fn new(mode: &ConfigMode, config: &Config, system: &System) -> Watcher {
+        match mode {
+            ConfigMode::ConfigInterval { .. } => Watcher::Interval(IntervalMetrics::new(config, system)),
+            ConfigMode::ConfigWarn { .. } => Watcher::Warn(WarnMetrics::new(config)),
+        }
+    }
+
+
+    // Whether the watcher runs the passed mode.
+    fn watches(&self, mode: &ConfigMode) -> bool {
+        matches!(
+            (self, mode),
+            (Watcher::Interval(_), ConfigMode::ConfigInterval { .. }) | (Watcher::Warn(_), ConfigMode::ConfigWarn { .. })
+        )
+    }
+}
+
+
+// A sink created from a logging method of the config, together with the credentials it was created with.
+type ConfigSink = ((LogType, LogCredentials), Box<dyn Sink>);
+
+
+// Runs every configured mode on its own period. The system is shared and refreshed once per tick,
+// after which the same snapshot is sent to every sink by each mode that is due.
+pub struct Scheduler {
+    system: System,
+
+    // Each watcher together with how often it runs and when it should run next.
+    schedule: Vec<(Watcher, Duration, Instant)>,
+
+    // The sinks of the type variable, created again when their credentials change on a reload
+    config_sinks: Vec<ConfigSink>,
+
+    // The sinks passed by a library user, kept as they are on a reload
+    extra_sinks: Vec<Box<dyn Sink>>,
+
+    // Sent with every sample, to tell where it was taken
+    hostname: String,
+    instance_name: Option<String>,
+}
+
+
+impl Scheduler {
+    // Create the sinks of the config, which are run next to the passed extra sinks. Fails if a sink can't be created.
+    pub fn new(config: &Config, extra_sinks: Vec<Box<dyn Sink>>) -> Result<Scheduler, String> {
+        let system = System::new_all();
+        let now = Instant::now();
+        let schedule = config.modes
+            .iter()
+            .map(|(mode, period)| {
+                let period = Duration::from_secs(*period as u64);
+                (Watcher::new(mode, config, &system), period, now + period)
+            })
+            .collect();
+
+        let hostname = system.get_host_name().unwrap_or_else(|| "unknown".into());
+        let config_sinks = config.logs
+            .iter()
+            .map(|log| Ok((log.clone(), logging::create_sink(&log.0, &log.1)?)))
+            .collect::<Result<Vec<ConfigSink>, String>>()?;
+
+        Ok(Scheduler {
+            system,
+            schedule,
+            config_sinks,
+            extra_sinks,
+            hostname,
+            instance_name: config.instance_name.clone(),
+        })
+    }
+
+
+    // Run the modes until the process is stopped. The config is reloaded on a SIGHUP, or once one of its
+    // files changes.
+    pub async fn run(mut self, mut sources: ConfigSources) {
+        let mut hangup = Hangup::listen();
+        let mut poll = tokio::time::interval(POLL_PERIOD);
+
+        loop {
+            tokio::select! {
+                _ = tokio::time::sleep_until(self.next_run()) => self.run_due().await,
+                _ = hangup.recv() => self.reload(&mut sources),
+                _ = poll.tick() => if sources.changed() { self.reload(&mut sources) },
+            }
+        }
+    }
+
+
+    // Wait until the next mode is due, refresh the system and run every mode which is due.
+    pub async fn tick(&mut self) {
+        tokio::time::sleep_until(self.next_run()).await;
+        self.run_due().await;
+    }
+
+
+    fn next_run(&self) -> Instant {
+        self.schedule
+            .iter()
+            .map(|(_, _, next_run)| *next_run)
+            .min()
+            .expect("The config needs to have at least one mode")
+    }
+
+
+    // Refresh the system and run every mode which is due. Isn't cancelled by a reload, so a sample is
+    // never sent to only some of the sinks.
+    async fn run_due(&mut self) {
+        let next_run = self.next_run();
+
+        self.system.refresh_all();
+        let sample = Sample::new(&self.system, &self.hostname, self.instance_name.as_deref());
+        let mut sinks = self.config_sinks.iter_mut().map(|(_, sink)| sink).chain(self.extra_sinks.iter_mut()).collect::<Vec<_>>();
+
+        for (watcher, period, watcher_next_run) in self.schedule.iter_mut() {
+            if *watcher_next_run > next_run { continue };
+            *watcher_next_run += *period;
+
+            match watcher {
+                Watcher::Interval(metrics) => {
+                    metrics.update_metrics(&self.system);
+
+                    for sink in sinks.iter_mut() {
+                        sink.emit_interval(metrics, &sample).await;
+                    }
+                },
+
+                Watcher::Warn(metrics) => {
+                    metrics.update_warns(&self.system);
+
+                    for sink in sinks.iter_mut() {
+                        sink.update_warns(metrics, &sample).await;
+
+                        // Nothing is logged unless a warning started, is due a reminder or resolved.
+                        if !metrics.alerts.is_empty() {
+                            sink.emit_warn(metrics, &sample).await;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+
+    fn reload(&mut self, sources: &mut ConfigSources) {
+        if let Some(config) = sources.reload() {
+            match self.apply(&config) {
+                Ok(()) => eprintln!("Reloaded the config"),
+                Err(err) => eprintln!("{}\nKeeping the previous config", err),
+            }
+        }
+    }
+
+
+    // Use a reloaded config. A mode which is still used keeps its metrics, so its firing warnings aren't sent
+    // again, and runs no later than its new period from now. Only the sinks whose logging method is new or has
+    // new credentials are created, the sinks of the removed logging methods are dropped. The new sinks are created
+    // first, so if one of them fails nothing is changed and the previous config keeps running.
+    pub fn apply(&mut self, config: &Config) -> Result<(), String> {
+        let mut new_sinks = Vec::new();
+
+        for log in config.logs.iter() {
+            if !self.config_sinks.iter().any(|(previous_log, _)| previous_log == log) {
+                new_sinks.push((log.clone(), logging::create_sink(&log.0, &log.1)?));
+            }
+        }
+
+        let now = Instant::now();
+        let mut previous_schedule = std::mem::take(&mut self.schedule);
+
+        self.schedule = config.modes
+            .iter()
+            .map(|(mode, period)| {
+                let period = Duration::from_secs(*period as u64);
+
+                match previous_schedule.iter().position(|(watcher, _, _)| watcher.watches(mode)) {
+                    Some(index) => {
+                        let (mut watcher, _, next_run) = previous_schedule.remove(index);
+
+                        match &mut watcher {
+                            Watcher::Interval(metrics) => metrics.reload(config, &self.system),
+                            Watcher::Warn(metrics) => metrics.reload(config),
+                        }
+
+                        (watcher, period, next_run.min(now + period))
+                    },
+                    None => (Watcher::new(mode, config, &self.system), period, now + period),
+                }
+            })
+            .collect();
+
+        let mut previous_sinks = std::mem::take(&mut self.config_sinks);
+
+        self.config_sinks = config.logs
+            .iter()
+            .map(|log| match previous_sinks.iter().position(|(previous_log, _)| previous_log == log) {
+                Some(index) => previous_sinks.remove(index),
+                None => {
+                    let index = new_sinks.iter().position(|(new_log, _)| new_log == log).expect("Every new sink was created");
+                    new_sinks.remove(index)
+                },
+            })
+            .collect();
+
+        self.instance_name = config.instance_name.clone();
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::logging::{Sink, Sample};
+    use crate::metrics::{interval::IntervalMetrics, warn::WarnMetrics};
+    use crate::parse_config::{Config, ConfigMode, LogType, LogCredentials};
+    use async_trait::async_trait;
+    use std::sync::{Arc, Mutex};
+    use std::time::Duration;
+    use super::Scheduler;
+
+
+    // Records which emit calls were made, so a test can check what the scheduler ran.
+    struct RecordingSink { calls: Arc<Mutex<Vec<&'static str>>> }
+
+    #[async_trait]
+    impl Sink for RecordingSink {
+        async fn emit_interval(&mut self, _metrics: &IntervalMetrics, sample: &Sample<'_>) {
+            self.calls.lock().unwrap().push("interval");
+            assert_eq!(sample.instance_name, Some("web-1"));
+        }
+
+        async fn emit_warn(&mut self, _metrics: &WarnMetrics, _sample: &Sample<'_>) {
+            self.calls.lock().unwrap().push("warn");
+        }
+
+        async fn update_warns(&mut self, metrics: &WarnMetrics, _sample: &Sample<'_>) {
+            if metrics.warnings.is_empty() { self.calls.lock().unwrap().push("no warnings") }
+        }
+    }
+
+
+    fn both_modes_config(interval_period: u32, warn_period: u32, ram_limit: f64) -> Config {
+        Config {
+            modes: vec![
+                (ConfigMode::ConfigInterval {
+                    ram: true,
+                    cpu: false,
+                    cpu_cores: false,
+                    cpu_average: false,
+                    system_uptime: false,
+                    disk: false,
+                    swap: false,
+                    processes: false,
+                    network: false,
+                    disk_io: false,
+                    temperature: false,
+                }, interval_period),
+                (ConfigMode::warn_limits(ram_limit, 0.0, 0.0, 0.0), warn_period),
+            ],
+            instance_name: Some("web-1".into()),
+            ..Config::default()
+        }
+    }
+
+
+    #[tokio::test]
+    async fn tick_runs_every_due_mode() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink = RecordingSink { calls: calls.clone() };
+        // Any used RAM is above a 1% limit.
+        let mut scheduler = Scheduler::new(&both_modes_config(0, 0, 1.0), vec![Box::new(sink)]).unwrap();
+
+        scheduler.tick().await;
+
+        assert_eq!(*calls.lock().unwrap(), vec!["interval", "warn"]);
+    }
+
+
+    #[tokio::test]
+    async fn tick_skips_modes_not_due() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink = RecordingSink { calls: calls.clone() };
+        let mut scheduler = Scheduler::new(&both_modes_config(3600, 0, 1.0), vec![Box::new(sink)]).unwrap();
+
+        scheduler.tick().await;
+        scheduler.tick().await;
+
+        // The RAM warning keeps firing on the second run, so it is only sent once.
+        assert_eq!(*calls.lock().unwrap(), vec!["warn"]);
+    }
+
+
+    #[tokio::test]
+    async fn tick_updates_warns_without_warnings() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink = RecordingSink { calls: calls.clone() };
+        // No system uses more than 100% of its RAM.
+        let mut scheduler = Scheduler::new(&both_modes_config(3600, 0, 100.0), vec![Box::new(sink)]).unwrap();
+
+        scheduler.tick().await;
+
+        assert_eq!(*calls.lock().unwrap(), vec!["no warnings"]);
+    }
+
+
+    #[tokio::test]
+    async fn apply_keeps_alert_state() {
+        let calls = Arc::new(Mutex::new(Vec::new()));
+        let sink = RecordingSink { calls: calls.clone() };
+        let mut scheduler = Scheduler::new(&both_modes_config(0, 0, 1.0), vec![Box::new(sink)]).unwrap();
+
+        scheduler.tick().await;
+
+        // The RAM is still above the new limit, so the firing warning isn't sent again. The interval mode
+        // was already due, so it still runs even though its new period is longer.
+        scheduler.apply(&both_modes_config(3600, 0, 2.0)).unwrap();
+        scheduler.tick().await;
+
+        // The warning resolves once the limit is raised above the used RAM.
+        scheduler.apply(&both_modes_config(3600, 0, 100.0)).unwrap();
+        scheduler.tick().await;
+
+        assert_eq!(*calls.lock().unwrap(), vec!["interval", "warn", "interval", "no warnings", "warn"]);
+    }
+
+
+    #[tokio::test]
+    async fn apply_keeps_previous_config_on_sink_failure() {
+        let prometheus = |address: &str| (LogType::Prometheus, LogCredentials::PrometheusLog { address: address.parse().unwrap() });
+        let used = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let used_address = used.local_addr().unwrap().to_string();
+
+        let mut scheduler = Scheduler::new(&Config { logs: vec![prometheus("127.0.0.1:0")], ..both_modes_config(0, 0, 1.0) }, vec![]).unwrap();
+        let result = scheduler.apply(&Config { logs: vec![prometheus(&used_address)], ..both_modes_config(3600, 0, 1.0) });
+
+        assert!(result.unwrap_err().starts_with(&format!("Couldn't listen on the {} prometheus_address", used_address)));
+        assert_eq!(scheduler.config_sinks.iter().map(|(log, _)| log.clone()).collect::<Vec<_>>(), vec![prometheus("127.0.0.1:0")]);
+        assert_eq!(scheduler.schedule[0].1, Duration::from_secs(0));
+    }
+}
@@ -0,0 +1,12 @@
+// The config is parsed from env variables, which collide with each other when the tests use them from more than
+// 1 thread. Each test which uses them holds ENV_LOCK for its whole duration, so they are run one at a time.
+use std::sync::{Mutex, MutexGuard};
+
+
+static ENV_LOCK: Mutex<()> = Mutex::new(());
+
+
+// A test which panics on purpose poisons the lock, which is fine since it guards no data.
+pub fn lock_env() -> MutexGuard<'static, ()> {
+    ENV_LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}